@@ -1,25 +1,228 @@
-use clap::{Args, Parser, Subcommand};
+use std::{num::NonZeroUsize, path::PathBuf};
 
-use crate::event_history::HistorySize;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    event_history::HistorySize, focus_action::FocusAction, monitor_selector::MonitorSelector,
+    scope::Scope, window_filter::ValuePattern,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Args)]
 pub struct FocusCommandArgs {
+    /// Monitor name, `desc:` description glob or `re:` name regex, negated with a leading `!`
     #[arg(long = "monitor")]
-    pub requested_monitors: Vec<String>,
+    pub requested_monitors: Vec<MonitorSelector>,
+    /// Use the monitors of a tracker from the configuration file
+    #[arg(long = "tracker", conflicts_with = "requested_monitors")]
+    pub tracker: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct TraverseArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// Number of history entries to move
+    #[arg(value_name = "COUNT", conflicts_with = "count")]
+    pub count_positional: Option<NonZeroUsize>,
+    /// Number of history entries to move
+    #[arg(long = "count")]
+    pub count: Option<NonZeroUsize>,
+    /// Only stop on windows of this class, or matching a `re:` class regex
+    #[arg(long = "class")]
+    pub class: Option<ValuePattern>,
+    /// Only stop on windows that are currently in this part of the desktop
+    #[arg(long = "scope", value_enum, default_value_t)]
+    pub scope: Scope,
+    /// Also stop on windows left on untracked monitors, moving them to the focused monitor
+    #[arg(long = "include-inactive")]
+    pub include_inactive: bool,
+    /// How to bring the window into focus [default: configured action, or focus]
+    #[arg(long = "action", value_enum)]
+    pub action: Option<FocusAction>,
+}
+
+impl TraverseArgs {
+    #[must_use]
+    pub fn steps(&self) -> NonZeroUsize {
+        self.count
+            .or(self.count_positional)
+            .unwrap_or(NonZeroUsize::MIN)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct GotoArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// History index, as reported by `focus list`
+    pub index: usize,
+    /// How to bring the window into focus [default: configured action, or focus]
+    #[arg(long = "action", value_enum)]
+    pub action: Option<FocusAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct LastArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// How to bring the window into focus [default: configured action, or focus]
+    #[arg(long = "action", value_enum)]
+    pub action: Option<FocusAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct FrecentArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// Position in the frecency ranking, starting at 1
+    pub rank: NonZeroUsize,
+    /// How to bring the window into focus [default: configured action, or focus]
+    #[arg(long = "action", value_enum)]
+    pub action: Option<FocusAction>,
+}
+
+/// Order of `focus list` output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ListSort {
+    /// Every history entry, oldest first
+    #[default]
+    History,
+    /// One entry per window, highest frecency first
+    Frecency,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct ListArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    #[arg(long = "sort", value_enum, default_value_t)]
+    pub sort: ListSort,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
 pub enum FocusCommand {
-    Next(FocusCommandArgs),
-    Prev(FocusCommandArgs),
+    Next(TraverseArgs),
+    Prev(TraverseArgs),
+    /// Move the history cursor to an index and focus its window
+    Goto(GotoArgs),
+    /// Focus the most recently focused window other than the current one
+    Last(LastArgs),
+    /// Focus the window at a rank by frecency, combining how often and how recently it was focused
+    Frecent(FrecentArgs),
+    /// Print the focus history as newline-delimited JSON
+    List(ListArgs),
+    /// Stream focus history changes as newline-delimited JSON
+    Watch(FocusCommandArgs),
+    /// Reload the daemon's configuration file
+    Reload(FocusCommandArgs),
+}
+
+impl FocusCommand {
+    #[must_use]
+    pub fn args(&self) -> &FocusCommandArgs {
+        match self {
+            FocusCommand::Next(TraverseArgs { focus: args, .. })
+            | FocusCommand::Prev(TraverseArgs { focus: args, .. })
+            | FocusCommand::Goto(GotoArgs { focus: args, .. })
+            | FocusCommand::Last(LastArgs { focus: args, .. })
+            | FocusCommand::Frecent(FrecentArgs { focus: args, .. })
+            | FocusCommand::List(ListArgs { focus: args, .. })
+            | FocusCommand::Watch(args)
+            | FocusCommand::Reload(args) => args,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct MarkArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// Single character naming the mark
+    pub mark: char,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct MarkJumpArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// Single character naming the mark
+    pub mark: char,
+    /// How to bring the window into focus [default: focus]
+    #[arg(long = "action", value_enum)]
+    pub action: Option<FocusAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum MarkCommand {
+    /// Mark the focused window
+    Set(MarkArgs),
+    /// Focus the marked window and record it in the focus history
+    Jump(MarkJumpArgs),
+    /// Print every mark as newline-delimited JSON
+    List(FocusCommandArgs),
+    /// Forget a mark
+    Clear(MarkArgs),
+}
+
+impl MarkCommand {
+    #[must_use]
+    pub fn args(&self) -> &FocusCommandArgs {
+        match self {
+            MarkCommand::Set(MarkArgs { focus: args, .. })
+            | MarkCommand::Clear(MarkArgs { focus: args, .. })
+            | MarkCommand::Jump(MarkJumpArgs { focus: args, .. })
+            | MarkCommand::List(args) => args,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct JumpArgs {
+    #[command(flatten)]
+    pub focus: FocusCommandArgs,
+    /// Class of the windows to jump between, or a `re:` class regex
+    #[arg(long = "class")]
+    pub class: ValuePattern,
+    /// Command Hyprland launches when no window matches
+    #[arg(long = "exec")]
+    pub exec: Option<String>,
+    /// How to bring the window into focus [default: focus]
+    #[arg(long = "action", value_enum)]
+    pub action: Option<FocusAction>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Args)]
 pub struct DaemonArgs {
+    /// Monitor name, `desc:` description glob or `re:` name regex, negated with a leading `!`
     #[arg(long = "monitor")]
-    pub requested_monitors: Vec<String>,
-    #[arg(long = "history-size", default_value_t = HistorySize::default())]
-    pub history_size: HistorySize,
+    pub requested_monitors: Vec<MonitorSelector>,
+    /// Apply the settings of a tracker from the configuration file
+    #[arg(long = "tracker")]
+    pub tracker: Option<String>,
+    /// Maximum number of focus events to track [default: 300]
+    #[arg(long = "history-size")]
+    pub history_size: Option<HistorySize>,
+    /// Seconds between resyncs of the history against Hyprland's window list; 0 disables
+    /// [default: 60]
+    #[arg(long = "reconcile-interval")]
+    pub reconcile_interval: Option<u64>,
+    /// Additional user ids allowed to connect to the daemon socket
+    #[arg(long = "allow-uid")]
+    pub allowed_uids: Vec<u32>,
+    /// Expose the focus history as `org.hyprhist` on the session bus
+    #[cfg(feature = "dbus")]
+    #[arg(long = "dbus")]
+    pub dbus: bool,
+}
+
+impl DaemonArgs {
+    #[must_use]
+    pub fn dbus(&self) -> bool {
+        #[cfg(feature = "dbus")]
+        return self.dbus;
+        #[cfg(not(feature = "dbus"))]
+        return false;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
@@ -37,11 +240,31 @@ pub enum Command {
         #[command(subcommand)]
         command: FocusCommand,
     },
+    Mark {
+        #[command(subcommand)]
+        command: MarkCommand,
+    },
+    /// Focus the most recently focused matching window, cycling through older ones on repeated
+    /// use, or launch a command when none matches
+    Jump(JumpArgs),
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum ConfigCommand {
+    /// Validate the configuration file and report errors with their line numbers
+    Check,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Parser)]
 #[command(name = "hyprhist", about = "hyprhist CLI")]
 pub struct Cli {
+    /// Configuration file [default: `$XDG_CONFIG_HOME/hyprhist/config.toml`]
+    #[arg(long = "config", global = true)]
+    pub config: Option<PathBuf>,
     #[command(subcommand)]
     pub command: Command,
 }
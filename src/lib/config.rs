@@ -0,0 +1,685 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    cli::{DaemonArgs, FocusCommand, FocusCommandArgs, JumpArgs, MarkJumpArgs},
+    event_history::HistorySize,
+    focus_action::FocusAction,
+    marks::default_marks_path,
+    monitor_selector::MonitorSelector,
+    window_filter::{WindowFilters, WindowRule},
+};
+
+const CONFIG_DIR: &str = "hyprhist";
+const CONFIG_FILE: &str = "config.toml";
+const DEFAULT_RECONCILE_INTERVAL: u64 = 60;
+
+/// `$XDG_CONFIG_HOME/hyprhist/config.toml`, falling back to `~/.config` when `XDG_CONFIG_HOME`
+/// isn't set
+#[must_use]
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|config_home| !config_home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join(CONFIG_DIR).join(CONFIG_FILE))
+}
+
+/// Window rules deciding which focused windows enter the history
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FiltersConfig {
+    include: Vec<Spanned<WindowRule>>,
+    exclude: Vec<Spanned<WindowRule>>,
+}
+
+impl FiltersConfig {
+    fn rules(&self) -> impl Iterator<Item = &Spanned<WindowRule>> {
+        self.include.iter().chain(&self.exclude)
+    }
+
+    fn window_filters(&self) -> WindowFilters {
+        WindowFilters {
+            include: self
+                .include
+                .iter()
+                .map(|rule| rule.get_ref().clone())
+                .collect(),
+            exclude: self
+                .exclude
+                .iter()
+                .map(|rule| rule.get_ref().clone())
+                .collect(),
+        }
+    }
+}
+
+/// Default focus action of each command that focuses a window
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActionsConfig {
+    next: Option<FocusAction>,
+    prev: Option<FocusAction>,
+    goto: Option<FocusAction>,
+    last: Option<FocusAction>,
+    frecent: Option<FocusAction>,
+    mark: Option<FocusAction>,
+    jump: Option<FocusAction>,
+}
+
+/// Settings of a daemon selected with `--tracker`, whose monitors are either a group or a list of
+/// monitor selectors
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TrackerConfig {
+    group: Option<Spanned<String>>,
+    monitors: Vec<Spanned<String>>,
+    history_size: Option<HistorySize>,
+    reconcile_interval: Option<u64>,
+    filters: Option<FiltersConfig>,
+    persist_marks: Option<bool>,
+}
+
+/// Contents of the configuration file. Top level settings apply to every daemon and are
+/// overridden by the selected tracker, which is in turn overridden by CLI flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    history_size: Option<HistorySize>,
+    reconcile_interval: Option<u64>,
+    allow_uids: Vec<u32>,
+    dbus: bool,
+    persist_marks: bool,
+    filters: FiltersConfig,
+    actions: ActionsConfig,
+    groups: BTreeMap<String, Vec<Spanned<String>>>,
+    trackers: BTreeMap<String, Spanned<TrackerConfig>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+fn line_of(source: &str, span: &Range<usize>) -> usize {
+    source[..span.start.min(source.len())].matches('\n').count() + 1
+}
+
+/// Settings of a focus daemon after applying the configuration file and CLI flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonSettings {
+    pub requested_monitors: Vec<MonitorSelector>,
+    pub history_size: HistorySize,
+    pub reconcile_interval: u64,
+    pub allowed_uids: Vec<u32>,
+    pub dbus: bool,
+    pub window_filters: WindowFilters,
+    /// Where marks are stored, when they persist across restarts
+    pub marks_path: Option<PathBuf>,
+    pub actions: ActionsConfig,
+}
+
+// D-Bus methods take no action argument, so they always use the configured one
+impl ActionsConfig {
+    #[must_use]
+    pub fn next(&self) -> FocusAction {
+        self.next.unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn prev(&self) -> FocusAction {
+        self.prev.unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn goto(&self) -> FocusAction {
+        self.goto.unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn last(&self) -> FocusAction {
+        self.last.unwrap_or_default()
+    }
+}
+
+impl Config {
+    /// Parses and validates `source`, reporting every problem with the line it was found on
+    #[allow(clippy::missing_errors_doc)]
+    pub fn parse(source: &str) -> Result<Self, Vec<ConfigError>> {
+        let config: Self = toml::from_str(source).map_err(|err| {
+            vec![ConfigError {
+                line: err.span().map(|span| line_of(source, &span)),
+                message: err.message().to_string(),
+            }]
+        })?;
+
+        let errors = config.validate(source);
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate(&self, source: &str) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        let mut check_selectors = |selectors: &[Spanned<String>]| {
+            for selector in selectors {
+                if let Err(message) = selector.get_ref().parse::<MonitorSelector>() {
+                    errors.push(ConfigError {
+                        line: Some(line_of(source, &selector.span())),
+                        message,
+                    });
+                }
+            }
+        };
+
+        for selectors in self.groups.values() {
+            check_selectors(selectors);
+        }
+
+        for tracker in self.trackers.values() {
+            check_selectors(&tracker.get_ref().monitors);
+        }
+
+        let tracker_filters = self
+            .trackers
+            .values()
+            .filter_map(|tracker| tracker.get_ref().filters.as_ref());
+        for rule in std::iter::once(&self.filters)
+            .chain(tracker_filters)
+            .flat_map(FiltersConfig::rules)
+        {
+            if rule.get_ref().is_empty() {
+                errors.push(ConfigError {
+                    line: Some(line_of(source, &rule.span())),
+                    message: "window rule must set at least one property".to_string(),
+                });
+            }
+        }
+
+        for (name, tracker) in &self.trackers {
+            let line = Some(line_of(source, &tracker.span()));
+            let TrackerConfig {
+                group, monitors, ..
+            } = tracker.get_ref();
+
+            match group {
+                Some(_) if !monitors.is_empty() => errors.push(ConfigError {
+                    line,
+                    message: format!("tracker '{name}' sets both group and monitors"),
+                }),
+                Some(group) if !self.groups.contains_key(group.get_ref()) => {
+                    errors.push(ConfigError {
+                        line: Some(line_of(source, &group.span())),
+                        message: format!(
+                            "tracker '{name}' refers to unknown group '{}'",
+                            group.get_ref()
+                        ),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        errors
+    }
+
+    /// Reads the configuration at `path`, or the defaults when there is no file there or no
+    /// path at all
+    #[allow(clippy::missing_errors_doc)]
+    pub fn load_or_default(path: Option<&Path>) -> anyhow::Result<Self> {
+        path.map_or_else(|| Ok(Self::default()), Self::load)
+    }
+
+    /// Reads the configuration at `path`, or the defaults when there is no file there
+    #[allow(clippy::missing_errors_doc)]
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        Self::parse(&source).map_err(|errors| {
+            let errors: Vec<String> = errors
+                .iter()
+                .map(|error| format!("{}: {error}", path.display()))
+                .collect();
+            anyhow::anyhow!("Invalid configuration\n{}", errors.join("\n"))
+        })
+    }
+
+    fn tracker(&self, name: &str) -> anyhow::Result<&TrackerConfig> {
+        self.trackers
+            .get(name)
+            .map(Spanned::get_ref)
+            .with_context(|| format!("No tracker named '{name}' is configured"))
+    }
+
+    fn tracker_monitors(
+        tracker: &TrackerConfig,
+        groups: &BTreeMap<String, Vec<Spanned<String>>>,
+    ) -> Vec<MonitorSelector> {
+        let selectors = match &tracker.group {
+            Some(group) => groups.get(group.get_ref()).map_or(&[][..], Vec::as_slice),
+            None => &tracker.monitors,
+        };
+
+        // Selectors were validated when the configuration was parsed
+        selectors
+            .iter()
+            .filter_map(|selector| selector.get_ref().parse().ok())
+            .collect()
+    }
+
+    /// Monitors a focus command is sent for: either `--monitor` or the monitors of `--tracker`
+    #[allow(clippy::missing_errors_doc)]
+    pub fn focus_monitors(&self, args: &FocusCommandArgs) -> anyhow::Result<Vec<MonitorSelector>> {
+        match &args.tracker {
+            Some(name) => Ok(Self::tracker_monitors(self.tracker(name)?, &self.groups)),
+            None => Ok(args.requested_monitors.clone()),
+        }
+    }
+
+    /// Focus action of a traversal command: `--action`, `move-to-monitor` when traversal includes
+    /// inactive windows, or the one configured for the command
+    #[must_use]
+    pub fn focus_action(&self, command: &FocusCommand) -> FocusAction {
+        let (flag, configured) = match command {
+            FocusCommand::Next(args) => (args.action, self.actions.next),
+            FocusCommand::Prev(args) => (args.action, self.actions.prev),
+            FocusCommand::Goto(args) => (args.action, self.actions.goto),
+            FocusCommand::Last(args) => (args.action, self.actions.last),
+            FocusCommand::Frecent(args) => (args.action, self.actions.frecent),
+            _ => (None, None),
+        };
+
+        let include_inactive = matches!(
+            command,
+            FocusCommand::Next(args) | FocusCommand::Prev(args) if args.include_inactive
+        );
+
+        flag.or(include_inactive.then_some(FocusAction::MoveToMonitor))
+            .or(configured)
+            .unwrap_or_default()
+    }
+
+    /// Focus action of `mark jump`: `--action`, or the one configured for marks
+    #[must_use]
+    pub fn mark_action(&self, args: &MarkJumpArgs) -> FocusAction {
+        args.action.or(self.actions.mark).unwrap_or_default()
+    }
+
+    /// Focus action of `jump`: `--action`, or the one configured for it
+    #[must_use]
+    pub fn jump_action(&self, args: &JumpArgs) -> FocusAction {
+        args.action.or(self.actions.jump).unwrap_or_default()
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn daemon_settings(&self, args: &DaemonArgs) -> anyhow::Result<DaemonSettings> {
+        let tracker = match &args.tracker {
+            Some(name) => Some(self.tracker(name)?),
+            None => None,
+        };
+
+        let requested_monitors = if args.requested_monitors.is_empty() {
+            tracker
+                .map(|tracker| Self::tracker_monitors(tracker, &self.groups))
+                .unwrap_or_default()
+        } else {
+            args.requested_monitors.clone()
+        };
+
+        let allowed_uids = if args.allowed_uids.is_empty() {
+            self.allow_uids.clone()
+        } else {
+            args.allowed_uids.clone()
+        };
+
+        Ok(DaemonSettings {
+            requested_monitors,
+            history_size: args
+                .history_size
+                .or_else(|| tracker.and_then(|tracker| tracker.history_size))
+                .or(self.history_size)
+                .unwrap_or_default(),
+            reconcile_interval: args
+                .reconcile_interval
+                .or_else(|| tracker.and_then(|tracker| tracker.reconcile_interval))
+                .or(self.reconcile_interval)
+                .unwrap_or(DEFAULT_RECONCILE_INTERVAL),
+            allowed_uids,
+            dbus: args.dbus() || self.dbus,
+            window_filters: tracker
+                .and_then(|tracker| tracker.filters.as_ref())
+                .unwrap_or(&self.filters)
+                .window_filters(),
+            marks_path: tracker
+                .and_then(|tracker| tracker.persist_marks)
+                .unwrap_or(self.persist_marks)
+                .then(|| default_marks_path(args.tracker.as_deref()))
+                .flatten(),
+            actions: self.actions.clone(),
+        })
+    }
+}
+
+/// Validates the configuration at `path`, printing every error with its line number
+#[allow(clippy::missing_errors_doc)]
+pub fn check(path: &Path) -> anyhow::Result<()> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            println!("{}: not found, defaults apply", path.display());
+            return Ok(());
+        }
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    match Config::parse(&source) {
+        Ok(_) => {
+            println!("{}: OK", path.display());
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                match error.line {
+                    Some(line) => eprintln!("{}:{line}: {}", path.display(), error.message),
+                    None => eprintln!("{}: {}", path.display(), error.message),
+                }
+            }
+            anyhow::bail!("{} error(s) in {}", errors.len(), path.display())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{Config, ConfigError};
+    use crate::{
+        cli::{Cli, Command, DaemonCommand, FocusCommand, MarkCommand},
+        focus_action::FocusAction,
+    };
+
+    const EXAMPLE: &str = r#"
+history-size = 500
+reconcile-interval = 30
+allow-uids = [1001]
+
+[groups]
+desk = ["desc:Dell Inc. DELL U2720Q*", "DP-2"]
+
+[trackers.desk]
+group = "desk"
+history-size = 100
+
+[trackers.laptop]
+monitors = ["eDP-1"]
+
+[trackers.laptop.filters]
+exclude = [{ floating = true }]
+
+[filters]
+exclude = [{ class = "re:^polkit" }, { title = "Picture-in-Picture" }]
+
+[actions]
+prev = "move-to-monitor"
+mark = "bring"
+jump = "move-to-monitor"
+"#;
+
+    fn daemon_args(args: &[&str]) -> crate::cli::DaemonArgs {
+        let cli = Cli::parse_from(["hyprhist", "daemon", "focus"].iter().chain(args));
+        match cli.command {
+            Command::Daemon {
+                command: DaemonCommand::Focus(args),
+            } => args,
+            Command::Focus { .. }
+            | Command::Mark { .. }
+            | Command::Jump(_)
+            | Command::Config { .. } => {
+                unreachable!()
+            }
+        }
+    }
+
+    fn focus_command(args: &[&str]) -> FocusCommand {
+        let cli = Cli::parse_from(["hyprhist", "focus"].iter().chain(args));
+        match cli.command {
+            Command::Focus { command } => command,
+            Command::Daemon { .. }
+            | Command::Mark { .. }
+            | Command::Jump(_)
+            | Command::Config { .. } => {
+                unreachable!()
+            }
+        }
+    }
+
+    fn monitor_sources(config: &Config, args: &[&str]) -> Vec<String> {
+        config
+            .daemon_settings(&daemon_args(args))
+            .expect("valid settings")
+            .requested_monitors
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn tracker_settings_override_top_level_settings() {
+        let config = Config::parse(EXAMPLE).expect("valid config");
+
+        let settings = config
+            .daemon_settings(&daemon_args(&["--tracker", "desk"]))
+            .expect("valid settings");
+
+        assert_eq!(settings.history_size.get(), 100);
+        assert_eq!(settings.reconcile_interval, 30);
+        assert_eq!(settings.allowed_uids, vec![1001]);
+        assert_eq!(
+            monitor_sources(&config, &["--tracker", "desk"]),
+            vec!["desc:Dell Inc. DELL U2720Q*", "DP-2"]
+        );
+        assert_eq!(
+            monitor_sources(&config, &["--tracker", "laptop"]),
+            vec!["eDP-1"]
+        );
+    }
+
+    #[test]
+    fn cli_flags_override_config() {
+        let config = Config::parse(EXAMPLE).expect("valid config");
+
+        let settings = config
+            .daemon_settings(&daemon_args(&[
+                "--tracker",
+                "desk",
+                "--history-size",
+                "7",
+                "--monitor",
+                "HDMI-A-1",
+            ]))
+            .expect("valid settings");
+
+        assert_eq!(settings.history_size.get(), 7);
+        assert_eq!(
+            settings
+                .requested_monitors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["HDMI-A-1"]
+        );
+    }
+
+    #[test]
+    fn defaults_apply_without_config() {
+        let settings = Config::default()
+            .daemon_settings(&daemon_args(&[]))
+            .expect("valid settings");
+
+        assert_eq!(settings.history_size.get(), 300);
+        assert_eq!(settings.reconcile_interval, 60);
+        assert!(settings.requested_monitors.is_empty());
+    }
+
+    #[test]
+    fn unknown_tracker_is_an_error() {
+        assert!(
+            Config::default()
+                .daemon_settings(&daemon_args(&["--tracker", "desk"]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn tracker_filters_replace_top_level_filters() {
+        let config = Config::parse(EXAMPLE).expect("valid config");
+        let window_filters = |args: &[&str]| {
+            config
+                .daemon_settings(&daemon_args(args))
+                .expect("valid settings")
+                .window_filters
+        };
+
+        assert_eq!(window_filters(&["--tracker", "desk"]).exclude.len(), 2);
+        assert_eq!(window_filters(&["--tracker", "laptop"]).exclude.len(), 1);
+    }
+
+    #[test]
+    fn configured_focus_actions_yield_to_the_action_flag() {
+        let config = Config::parse(EXAMPLE).expect("valid config");
+        let focus_action = |args: &[&str]| config.focus_action(&focus_command(args));
+
+        assert_eq!(focus_action(&["prev"]), FocusAction::MoveToMonitor);
+        assert_eq!(
+            focus_action(&["prev", "--action", "swap"]),
+            FocusAction::Swap
+        );
+        assert_eq!(focus_action(&["next"]), FocusAction::Focus);
+        assert_eq!(
+            focus_action(&["next", "--include-inactive"]),
+            FocusAction::MoveToMonitor
+        );
+        assert_eq!(
+            focus_action(&["next", "--include-inactive", "--action", "bring"]),
+            FocusAction::Bring
+        );
+        assert_eq!(
+            focus_action(&["goto", "3", "--action", "bring"]),
+            FocusAction::Bring
+        );
+    }
+
+    #[test]
+    fn settings_carry_configured_actions_for_dbus() {
+        let actions = Config::parse(EXAMPLE)
+            .expect("valid config")
+            .daemon_settings(&daemon_args(&[]))
+            .expect("valid settings")
+            .actions;
+
+        assert_eq!(actions.prev(), FocusAction::MoveToMonitor);
+        assert_eq!(actions.next(), FocusAction::Focus);
+    }
+
+    #[test]
+    fn configured_mark_action_yields_to_the_action_flag() {
+        let config = Config::parse(EXAMPLE).expect("valid config");
+        let mark_action = |args: &[&str]| {
+            let cli = Cli::parse_from(["hyprhist", "mark", "jump", "a"].iter().chain(args));
+            match cli.command {
+                Command::Mark {
+                    command: MarkCommand::Jump(args),
+                } => config.mark_action(&args),
+                Command::Daemon { .. }
+                | Command::Focus { .. }
+                | Command::Mark { .. }
+                | Command::Jump(_)
+                | Command::Config { .. } => unreachable!(),
+            }
+        };
+
+        assert_eq!(mark_action(&[]), FocusAction::Bring);
+        assert_eq!(mark_action(&["--action", "swap"]), FocusAction::Swap);
+    }
+
+    #[test]
+    fn configured_jump_action_yields_to_the_action_flag() {
+        let config = Config::parse(EXAMPLE).expect("valid config");
+        let jump_action = |args: &[&str]| {
+            let cli = Cli::parse_from(["hyprhist", "jump", "--class", "kitty"].iter().chain(args));
+            match cli.command {
+                Command::Jump(args) => config.jump_action(&args),
+                Command::Daemon { .. }
+                | Command::Focus { .. }
+                | Command::Mark { .. }
+                | Command::Config { .. } => unreachable!(),
+            }
+        };
+
+        assert_eq!(jump_action(&[]), FocusAction::MoveToMonitor);
+        assert_eq!(jump_action(&["--action", "focus"]), FocusAction::Focus);
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_line_numbers() {
+        let errors = Config::parse("history-size = 10\nhistory-sise = 10\n").expect_err("invalid");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(2));
+    }
+
+    #[test]
+    fn reports_every_validation_error_with_line_numbers() {
+        let source = r#"[groups]
+desk = ["DP-1", "re:("]
+
+[trackers.desk]
+group = "dsek"
+
+[trackers.laptop]
+group = "desk"
+monitors = ["a/b"]
+
+[filters]
+exclude = [{ class = "kitty" }, {}]
+"#;
+
+        let errors = Config::parse(source).expect_err("invalid");
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|ConfigError { line, .. }| *line)
+                .collect::<Vec<_>>(),
+            vec![Some(2), Some(9), Some(12), Some(5), Some(7)]
+        );
+    }
+}
@@ -1,131 +1,376 @@
 use chrono::Local;
 use hyprland::{
-    event_listener::{AsyncEventListener, WindowEventData, WindowMoveEvent},
+    event_listener::{
+        AsyncEventListener, MonitorAddedEventData, WindowEventData, WindowFloatEventData,
+        WindowMoveEvent, WindowOpenEvent, WindowPinEventData, WindowTitleEventData,
+        WorkspaceEventData, WorkspaceMovedEventData,
+    },
     shared::Address,
 };
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use std::{future::Future, pin::Pin};
+use tokio::time::{Duration, Instant, MissedTickBehavior, interval, sleep};
 
 use crate::{
-    hypr_utils::{WindowMonitorRequest, get_window_monitor_request},
-    types::{FocusEvents, HyprEvents, SharedEventHistory, SortedDistinctVec, WindowEvent},
+    focus_history::{FocusHistory, FocusMessage},
+    hypr_utils::current_focused_window_event,
+    reload::Reloader,
+    types::{FocusEvents, HyprEvents},
 };
 
 type ListenerFuture<T> =
     Box<dyn Fn(T) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync + 'static>;
 
-fn window_closed_handler(focus_events: SharedEventHistory<WindowEvent>) -> ListenerFuture<Address> {
-    Box::new(move |address: Address| {
-        debug!("Window closed event occured: {address:?}");
-        let focus_events = focus_events.clone();
-        Box::pin(async move {
-            let mut event_history = focus_events.lock().await;
-            event_history.remove(&address.to_string());
-        })
+/// Queues a message for every event as soon as it's received. The message is sent before the
+/// returned future is polled, so the focus history sees events in the order Hyprland sent them
+fn forward_handler<T: std::fmt::Debug + 'static>(
+    focus_events: &FocusHistory,
+    to_message: fn(T) -> Option<FocusMessage>,
+) -> ListenerFuture<T> {
+    let focus_events = focus_events.clone();
+    Box::new(move |event: T| {
+        debug!("Hyprland event occured: {event:?}");
+        if let Some(message) = to_message(event) {
+            focus_events.send(message);
+        }
+        Box::pin(async {})
     })
 }
 
-fn window_moved_handler(
-    focus_events: SharedEventHistory<WindowEvent>,
-    requested_monitors: &'static SortedDistinctVec<String>,
-) -> ListenerFuture<WindowMoveEvent> {
-    Box::new(move |window_move_event: WindowMoveEvent| {
-        debug!("Window move event occured: {window_move_event:?}");
-        let focus_events = focus_events.clone();
-
-        Box::pin(async move {
-            match get_window_monitor_request(&window_move_event.window_address, requested_monitors)
-                .await
-            {
-                WindowMonitorRequest::Matching { window_monitor } => {
-                    let time = Local::now().naive_local();
-                    let mut focus_history = focus_events.lock().await;
-                    focus_history.activate(&window_move_event.window_address.to_string());
-                    focus_history.add(WindowEvent {
-                        address: window_move_event.window_address.to_string(),
-                        monitor: Some(window_monitor),
-                        time,
-                    });
-                }
-                WindowMonitorRequest::NoMatch => {
-                    let mut focus_history = focus_events.lock().await;
-                    focus_history.deactivate(&window_move_event.window_address.to_string());
-                }
-                WindowMonitorRequest::AllRequested { window_monitor: _ } => {
-                    // Active/Inactive windows aren't necessary if all monitors are tracked
-                }
-            }
+fn focus_event_listener(focus_events: &FocusHistory, reloader: &Reloader) -> AsyncEventListener {
+    let mut event_listener = AsyncEventListener::new();
+
+    event_listener.add_window_opened_handler(forward_handler(
+        focus_events,
+        |window_open_event: WindowOpenEvent| {
+            Some(FocusMessage::WindowOpened {
+                address: window_open_event.window_address.to_string(),
+                workspace: window_open_event.workspace_name,
+                class: window_open_event.window_class,
+                title: window_open_event.window_title,
+            })
+        },
+    ));
+
+    event_listener.add_window_closed_handler(forward_handler(focus_events, |address: Address| {
+        Some(FocusMessage::WindowClosed {
+            address: address.to_string(),
         })
-    })
+    }));
+
+    event_listener.add_window_moved_handler(forward_handler(
+        focus_events,
+        |window_move_event: WindowMoveEvent| {
+            Some(FocusMessage::WindowMoved {
+                address: window_move_event.window_address.to_string(),
+                workspace: window_move_event.workspace_name.to_string(),
+                workspace_id: window_move_event.workspace_id,
+                time: Local::now().naive_local(),
+            })
+        },
+    ));
+
+    event_listener.add_window_title_changed_handler(forward_handler(
+        focus_events,
+        |window_title_event: WindowTitleEventData| {
+            Some(FocusMessage::WindowTitleChanged {
+                address: window_title_event.address.to_string(),
+                title: window_title_event.title,
+            })
+        },
+    ));
+
+    event_listener.add_float_state_changed_handler(forward_handler(
+        focus_events,
+        |window_float_event: WindowFloatEventData| {
+            Some(FocusMessage::WindowFloatingChanged {
+                address: window_float_event.address.to_string(),
+                floating: window_float_event.floating,
+            })
+        },
+    ));
+
+    event_listener
+        .add_fullscreen_state_changed_handler(forward_handler(focus_events, |fullscreen: bool| {
+            Some(FocusMessage::FullscreenChanged { fullscreen })
+        }));
+
+    event_listener.add_window_pinned_handler(forward_handler(
+        focus_events,
+        |window_pin_event: WindowPinEventData| {
+            Some(FocusMessage::WindowPinnedChanged {
+                address: window_pin_event.address.to_string(),
+                pinned: window_pin_event.pinned,
+            })
+        },
+    ));
+
+    event_listener.add_workspace_moved_handler(forward_handler(
+        focus_events,
+        |workspace_moved_event: WorkspaceMovedEventData| {
+            Some(FocusMessage::WorkspaceMoved {
+                workspace: workspace_moved_event.name.to_string(),
+                monitor: workspace_moved_event.monitor,
+            })
+        },
+    ));
+
+    event_listener.add_workspace_deleted_handler(forward_handler(
+        focus_events,
+        |workspace_event: WorkspaceEventData| {
+            Some(FocusMessage::WorkspaceDeleted {
+                workspace: workspace_event.name.to_string(),
+            })
+        },
+    ));
+
+    event_listener
+        .add_monitor_added_handler(forward_handler(focus_events, |_: MonitorAddedEventData| {
+            Some(FocusMessage::MonitorsChanged)
+        }));
+
+    event_listener.add_monitor_removed_handler(forward_handler(focus_events, |_: String| {
+        Some(FocusMessage::MonitorsChanged)
+    }));
+
+    event_listener.add_active_window_changed_handler(forward_handler(
+        focus_events,
+        |maybe_window_event_data: Option<WindowEventData>| {
+            maybe_window_event_data.map(|window_event_data| FocusMessage::ActiveWindowChanged {
+                address: window_event_data.address.to_string(),
+                time: Local::now().naive_local(),
+            })
+        },
+    ));
+
+    let reloader = reloader.clone();
+    event_listener.add_config_reloaded_handler(move || {
+        debug!("Hyprland configuration reloaded");
+        reloader.trigger();
+        Box::pin(async {})
+    });
+
+    event_listener
 }
 
-fn active_window_changed_handler(
-    focus_events: SharedEventHistory<WindowEvent>,
-    requested_monitors: &'static SortedDistinctVec<String>,
-) -> ListenerFuture<Option<WindowEventData>> {
-    Box::new(move |maybe_window_event_data| {
-        debug!("Active window event occured: {maybe_window_event_data:?}");
-        let focus_events = focus_events.clone();
-
-        Box::pin(async move {
-            let now_time = Local::now().naive_local();
-            let Some(window_event_data) = maybe_window_event_data else {
-                return;
-            };
-
-            match get_window_monitor_request(&window_event_data.address, requested_monitors).await {
-                WindowMonitorRequest::Matching {
-                    window_monitor: monitor,
-                }
-                | WindowMonitorRequest::AllRequested {
-                    window_monitor: monitor,
-                } => {
-                    let mut event_history = focus_events.lock().await;
-
-                    let window_event = WindowEvent {
-                        monitor: Some(monitor),
-                        address: window_event_data.address.to_string(),
-                        time: now_time,
-                    };
-
-                    if let Some(WindowEvent {
-                        address,
-                        time,
-                        monitor: _,
-                    }) = event_history.add(window_event)
-                    {
-                        info!("Registered active window event with id {address} at {time}");
-                    }
-                }
-                WindowMonitorRequest::NoMatch => {}
-            }
-        })
-    })
+/// Brings the history back in line with Hyprland after reconnecting, and registers the
+/// currently focused window again
+async fn reconcile_after_reconnect(focus_events: &FocusHistory) -> anyhow::Result<()> {
+    focus_events.reconcile().await?;
+
+    if let Some(window_event) = current_focused_window_event().await {
+        focus_events.send(FocusMessage::FocusedWindow(window_event));
+    }
+
+    Ok(())
+}
+
+async fn run_periodic_reconciliation(
+    focus_events: FocusHistory,
+    reconcile_interval: Option<Duration>,
+) -> anyhow::Result<()> {
+    let Some(reconcile_interval) = reconcile_interval else {
+        return Ok(());
+    };
+
+    info!("Reconciling focus history every {reconcile_interval:?}");
+    let mut ticker = interval(reconcile_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes immediately, and the history was only just bootstrapped
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        if let Err(err) = focus_events.reconcile().await {
+            warn!("Failed to reconcile focus history: {err}");
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Backoff {
+    next_delay: Duration,
+}
+
+impl Backoff {
+    const INITIAL_DELAY: Duration = Duration::from_millis(500);
+    const MAX_DELAY: Duration = Duration::from_secs(30);
+
+    fn new() -> Self {
+        Self {
+            next_delay: Self::INITIAL_DELAY,
+        }
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.next_delay;
+        self.next_delay = (delay * 2).min(Self::MAX_DELAY);
+        delay
+    }
+
+    fn reset(&mut self) {
+        self.next_delay = Self::INITIAL_DELAY;
+    }
+}
+
+/// A connection which stayed up for this long resets the reconnection backoff
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+/// Consecutive failed reconnection attempts after which Hyprland is assumed to be gone for good
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Retries `attempt` with the delays of `backoff` until it succeeds. Gives up after
+/// [`MAX_RECONNECT_ATTEMPTS`] failures in a row, as a restarted Hyprland has a new instance
+/// signature which only a restarted daemon picks up.
+async fn reconnect<F, Fut>(backoff: &mut Backoff, mut attempt: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut failures = 0;
+    loop {
+        let Err(err) = attempt().await else {
+            return Ok(());
+        };
+
+        failures += 1;
+        if failures >= MAX_RECONNECT_ATTEMPTS {
+            return Err(err.context(format!(
+                "Hyprland is still unavailable after {failures} attempts; if it restarted, the \
+                 daemon has to be restarted with the new HYPRLAND_INSTANCE_SIGNATURE"
+            )));
+        }
+
+        let delay = backoff.next_delay();
+        warn!("Hyprland is unavailable ({err}); retrying in {delay:?}");
+        sleep(delay).await;
+    }
+}
+
+async fn run_focus_listener(focus_events: FocusHistory, reloader: Reloader) -> anyhow::Result<()> {
+    let mut backoff = Backoff::new();
+    let mut reconnecting = false;
+
+    loop {
+        if reconnecting {
+            reconnect(&mut backoff, || reconcile_after_reconnect(&focus_events)).await?;
+        }
+
+        info!("Starting hyprland event listener");
+        let started_at = Instant::now();
+
+        match focus_event_listener(&focus_events, &reloader)
+            .start_listener_async()
+            .await
+        {
+            Ok(()) => warn!("Hyprland event socket closed"),
+            Err(err) => error!("Hyprland event listener failed: {err}"),
+        }
+
+        if started_at.elapsed() >= STABLE_CONNECTION {
+            backoff.reset();
+        }
+
+        let delay = backoff.next_delay();
+        info!("Reconnecting to hyprland in {delay:?}");
+        sleep(delay).await;
+        reconnecting = true;
+    }
 }
 
 #[allow(clippy::missing_errors_doc)]
 pub async fn run(hypr_events: HyprEvents) -> anyhow::Result<()> {
-    let mut event_listener = AsyncEventListener::new();
     match hypr_events {
         HyprEvents::Focus(FocusEvents {
             focus_events,
-            requested_monitors,
+            reloader,
+            reconcile_interval,
+            ..
         }) => {
-            event_listener.add_window_closed_handler(window_closed_handler(focus_events.clone()));
+            tokio::try_join!(
+                run_focus_listener(focus_events.clone(), reloader),
+                run_periodic_reconciliation(focus_events, reconcile_interval)
+            )?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
 
-            event_listener.add_active_window_changed_handler(active_window_changed_handler(
-                focus_events.clone(),
-                requested_monitors,
-            ));
+    use super::{Backoff, MAX_RECONNECT_ATTEMPTS, reconnect};
 
-            event_listener.add_window_moved_handler(window_moved_handler(
-                focus_events.clone(),
-                requested_monitors,
-            ));
+    fn instant_backoff() -> Backoff {
+        Backoff {
+            next_delay: Duration::ZERO,
         }
     }
 
-    info!("Starting hyprland event listener");
-    event_listener.start_listener_async().await?;
-    Ok(())
+    #[test]
+    fn backoff_doubles_until_max_delay() {
+        let mut backoff = Backoff::new();
+
+        let delays: Vec<_> = (0..8).map(|_| backoff.next_delay()).collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_millis(500),
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(8),
+                Duration::from_secs(16),
+                Duration::from_secs(30),
+                Duration::from_secs(30),
+            ]
+        );
+    }
+
+    #[test]
+    fn backoff_reset_restarts_from_initial_delay() {
+        let mut backoff = Backoff::new();
+        backoff.next_delay();
+        backoff.next_delay();
+
+        backoff.reset();
+
+        assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn reconnect_gives_up_after_consecutive_failures() {
+        let mut attempts = 0;
+
+        let result = reconnect(&mut instant_backoff(), || {
+            attempts += 1;
+            async { Err(anyhow::anyhow!("socket is gone")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts, MAX_RECONNECT_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn reconnect_succeeds_once_an_attempt_does() {
+        let mut attempts = 0;
+
+        let result = reconnect(&mut instant_backoff(), || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt < 3 {
+                    Err(anyhow::anyhow!("socket is gone"))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+    }
 }
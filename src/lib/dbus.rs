@@ -0,0 +1,468 @@
+use std::num::NonZeroUsize;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast::error::RecvError, watch};
+use zbus::{
+    Connection, fdo, interface, names::WellKnownName, object_server::SignalEmitter, zvariant::Type,
+};
+
+use crate::{
+    config::ActionsConfig,
+    event_history::{EntryStatus, HistoryChange},
+    focus_history::FocusHistory,
+    socket::{SocketInstruction, navigate_focus_history},
+    types::{FocusEvents, HyprEvents},
+};
+
+const BUS_NAME: &str = "org.hyprhist";
+const OBJECT_PATH: &str = "/org/hyprhist/Focus";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Type)]
+// Fields map one to one onto the D-Bus struct signature, so the flags stay plain booleans
+#[allow(clippy::struct_excessive_bools)]
+pub struct DbusWindow {
+    pub index: u64,
+    pub address: String,
+    pub monitor: String,
+    pub time: String,
+    pub class: String,
+    pub title: String,
+    pub initial_class: String,
+    pub initial_title: String,
+    pub workspace: String,
+    pub workspace_id: i32,
+    pub pid: i32,
+    pub floating: bool,
+    pub fullscreen: bool,
+    pub pinned: bool,
+    pub xwayland: bool,
+    pub active: bool,
+    pub current: bool,
+}
+
+struct FocusInterface {
+    focus_events: FocusHistory,
+    /// Configured actions, updated on every reload
+    actions: watch::Receiver<ActionsConfig>,
+}
+
+fn step_count(count: u32) -> fdo::Result<NonZeroUsize> {
+    usize::try_from(count)
+        .ok()
+        .and_then(NonZeroUsize::new)
+        .ok_or_else(|| fdo::Error::InvalidArgs("count must be a positive integer".to_string()))
+}
+
+#[interface(name = "org.hyprhist.Focus")]
+impl FocusInterface {
+    /// Moves `count` entries forward and returns the focused address, or an empty string
+    async fn next(&self, count: u32) -> fdo::Result<String> {
+        let instruction = SocketInstruction::Next {
+            count: step_count(count)?,
+            class: None,
+            scope: None,
+            include_inactive: false,
+            action: self.actions.borrow().next(),
+        };
+        Ok(navigate_focus_history(instruction, &self.focus_events)
+            .await
+            .unwrap_or_default())
+    }
+
+    /// Moves `count` entries backward and returns the focused address, or an empty string
+    async fn prev(&self, count: u32) -> fdo::Result<String> {
+        let instruction = SocketInstruction::Prev {
+            count: step_count(count)?,
+            class: None,
+            scope: None,
+            include_inactive: false,
+            action: self.actions.borrow().prev(),
+        };
+        Ok(navigate_focus_history(instruction, &self.focus_events)
+            .await
+            .unwrap_or_default())
+    }
+
+    /// Moves the cursor to `index` and returns the focused address, or an empty string
+    async fn goto(&self, index: u64) -> fdo::Result<String> {
+        let index = usize::try_from(index)
+            .map_err(|_| fdo::Error::InvalidArgs("index is out of range".to_string()))?;
+        let instruction = SocketInstruction::Goto {
+            index,
+            action: self.actions.borrow().goto(),
+        };
+        Ok(navigate_focus_history(instruction, &self.focus_events)
+            .await
+            .unwrap_or_default())
+    }
+
+    /// Focuses the most recent window other than the current one and returns its address, or an
+    /// empty string
+    async fn last(&self) -> fdo::Result<String> {
+        let instruction = SocketInstruction::Last {
+            action: self.actions.borrow().last(),
+        };
+        Ok(navigate_focus_history(instruction, &self.focus_events)
+            .await
+            .unwrap_or_default())
+    }
+
+    async fn list(&self) -> fdo::Result<Vec<DbusWindow>> {
+        let windows = self
+            .focus_events
+            .list()
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        Ok(windows
+            .into_iter()
+            .map(|window| DbusWindow {
+                index: window.index as u64,
+                address: window.address,
+                monitor: window.monitor.unwrap_or_default(),
+                time: window.time.to_string(),
+                class: window.properties.class,
+                title: window.properties.title,
+                initial_class: window.properties.initial_class,
+                initial_title: window.properties.initial_title,
+                workspace: window.properties.workspace,
+                workspace_id: window.properties.workspace_id,
+                pid: window.properties.pid,
+                floating: window.properties.floating,
+                fullscreen: window.properties.fullscreen,
+                pinned: window.properties.pinned,
+                xwayland: window.properties.xwayland,
+                active: window.status == EntryStatus::Active,
+                current: window.current,
+            })
+            .collect())
+    }
+
+    #[zbus(signal)]
+    async fn added(emitter: &SignalEmitter<'_>, id: &str, cursor: u64) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn removed(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn deactivated(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn activated(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn cursor_moved(emitter: &SignalEmitter<'_>, id: &str, cursor: u64) -> zbus::Result<()>;
+}
+
+async fn emit_history_change(
+    emitter: &SignalEmitter<'_>,
+    change: HistoryChange<String>,
+) -> zbus::Result<()> {
+    match change {
+        HistoryChange::Added { id, cursor } => {
+            FocusInterface::added(emitter, &id, cursor as u64).await
+        }
+        HistoryChange::Removed { id } => FocusInterface::removed(emitter, &id).await,
+        HistoryChange::Deactivated { id } => FocusInterface::deactivated(emitter, &id).await,
+        HistoryChange::Activated { id } => FocusInterface::activated(emitter, &id).await,
+        HistoryChange::CursorMoved { id, cursor } => {
+            FocusInterface::cursor_moved(emitter, id.as_deref().unwrap_or_default(), cursor as u64)
+                .await
+        }
+    }
+}
+
+/// Exports the focus interface on `connection` and emits a signal for every history change
+/// until the focus history stops running
+async fn serve_on(
+    connection: &Connection,
+    focus_events: FocusHistory,
+    actions: watch::Receiver<ActionsConfig>,
+) -> anyhow::Result<()> {
+    let mut changes = focus_events.subscribe().await?;
+
+    connection
+        .object_server()
+        .at(
+            OBJECT_PATH,
+            FocusInterface {
+                focus_events,
+                actions,
+            },
+        )
+        .await?;
+
+    let emitter = SignalEmitter::new(connection, OBJECT_PATH)?;
+
+    loop {
+        let change = match changes.recv().await {
+            Ok(change) => change,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("D-Bus signal emitter lagged; {skipped} changes were dropped");
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        if let Err(err) = emit_history_change(&emitter, change).await {
+            warn!("Failed to emit focus history signal: {err}");
+        }
+    }
+}
+
+#[allow(clippy::missing_errors_doc)]
+pub async fn serve(hypr_events: HyprEvents) -> anyhow::Result<()> {
+    match hypr_events {
+        HyprEvents::Focus(FocusEvents {
+            focus_events,
+            actions,
+            ..
+        }) => {
+            let connection = Connection::session().await?;
+
+            // Mirror the socket behaviour where the most recently started daemon wins
+            connection
+                .request_name_with_flags(
+                    WellKnownName::try_from(BUS_NAME)?,
+                    fdo::RequestNameFlags::AllowReplacement
+                        | fdo::RequestNameFlags::ReplaceExisting
+                        | fdo::RequestNameFlags::DoNotQueue,
+                )
+                .await?;
+
+            info!("Serving focus history on D-Bus as {BUS_NAME} at {OBJECT_PATH}");
+            serve_on(&connection, focus_events, actions).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::PathBuf,
+        process::{Child, Command, Stdio},
+        time::Duration,
+    };
+
+    use chrono::NaiveDateTime;
+    use futures::StreamExt;
+    use tokio::sync::watch;
+    use zbus::{Connection, Proxy, connection::Builder};
+
+    use super::{DbusWindow, OBJECT_PATH, serve_on};
+    use crate::{
+        config::ActionsConfig,
+        event_history::{EventHistory, HistorySize},
+        focus_history::{FocusHistory, FocusMessage},
+        marks::Marks,
+        monitor_selector::TrackedMonitors,
+        types::{SortedDistinctVec, WindowEvent},
+        window_cache::{WindowCache, WindowProperties},
+        window_filter::WindowFilters,
+    };
+
+    struct PrivateBus {
+        daemon: Child,
+        dir: PathBuf,
+        address: String,
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// Starts a throwaway `dbus-daemon`
+    fn private_bus(name: &str) -> PrivateBus {
+        let dir = std::env::temp_dir().join(format!("hyprhist-dbus-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create bus directory");
+        let socket = dir.join("bus");
+        let config = dir.join("bus.conf");
+        std::fs::write(
+            &config,
+            format!(
+                r#"<busconfig>
+  <type>session</type>
+  <listen>unix:path={}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>"#,
+                socket.display()
+            ),
+        )
+        .expect("write bus config");
+
+        let daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .arg("--nofork")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("dbus-daemon is installed");
+
+        let bus = PrivateBus {
+            daemon,
+            address: format!("unix:path={}", socket.display()),
+            dir,
+        };
+
+        for _ in 0..100 {
+            if socket.exists() {
+                return bus;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        panic!("dbus-daemon didn't create {}", socket.display());
+    }
+
+    fn window_event(address: &str) -> WindowEvent {
+        WindowEvent {
+            address: address.to_string(),
+            monitor: Some("DP-1".to_string()),
+            time: NaiveDateTime::default(),
+            properties: WindowProperties {
+                class: "kitty".to_string(),
+                title: format!("window {address}"),
+                ..WindowProperties::default()
+            },
+        }
+    }
+
+    fn spawn_focus_history(addresses: &[&str]) -> FocusHistory {
+        let mut history = EventHistory::new(HistorySize::default());
+        for address in addresses {
+            history.add(window_event(address));
+        }
+        let (focus_history, actor) = FocusHistory::new(
+            history,
+            WindowCache::default(),
+            TrackedMonitors::new(SortedDistinctVec::new(Vec::new())),
+            WindowFilters::default(),
+            Marks::default(),
+        );
+        tokio::spawn(actor.run());
+        focus_history
+    }
+
+    async fn connect(bus: &PrivateBus) -> Connection {
+        Builder::address(bus.address.as_str())
+            .expect("valid bus address")
+            .build()
+            .await
+            .expect("connect to private bus")
+    }
+
+    async fn focus_proxy<'a>(client: &'a Connection, server: &Connection) -> Proxy<'a> {
+        Proxy::new(
+            client,
+            server
+                .unique_name()
+                .expect("server has a unique name")
+                .to_owned(),
+            OBJECT_PATH,
+            "org.hyprhist.Focus",
+        )
+        .await
+        .expect("create proxy")
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon"]
+    async fn lists_and_traverses_history_over_dbus() {
+        let bus = private_bus("traverse");
+        let server = connect(&bus).await;
+        let client = connect(&bus).await;
+        let focus_events = spawn_focus_history(&["0x1", "0x2", "0x3"]);
+
+        let server_task = tokio::spawn({
+            let server = server.clone();
+            let focus_events = focus_events.clone();
+            async move {
+                serve_on(
+                    &server,
+                    focus_events,
+                    watch::channel(ActionsConfig::default()).1,
+                )
+                .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let proxy = focus_proxy(&client, &server).await;
+
+        let previous: String = proxy.call("Prev", &(2u32)).await.expect("call Prev");
+        assert_eq!(previous, "0x1");
+
+        let listed: Vec<DbusWindow> = proxy.call("List", &()).await.expect("call List");
+        assert_eq!(
+            listed
+                .iter()
+                .map(|window| (
+                    window.address.as_str(),
+                    window.title.as_str(),
+                    window.current
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("0x1", "window 0x1", true),
+                ("0x2", "window 0x2", false),
+                ("0x3", "window 0x3", false)
+            ]
+        );
+
+        let jumped: String = proxy.call("Goto", &(2u64)).await.expect("call Goto");
+        assert_eq!(jumped, "0x3");
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon"]
+    async fn emits_signals_for_history_changes() {
+        let bus = private_bus("signals");
+        let server = connect(&bus).await;
+        let client = connect(&bus).await;
+        let focus_events = spawn_focus_history(&["0x1"]);
+
+        let server_task = tokio::spawn({
+            let server = server.clone();
+            let focus_events = focus_events.clone();
+            async move {
+                serve_on(
+                    &server,
+                    focus_events,
+                    watch::channel(ActionsConfig::default()).1,
+                )
+                .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let proxy = focus_proxy(&client, &server).await;
+        let mut added = proxy
+            .receive_signal("Added")
+            .await
+            .expect("subscribe to Added");
+
+        focus_events.send(FocusMessage::FocusedWindow(window_event("0x2")));
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), added.next())
+            .await
+            .expect("signal before timeout")
+            .expect("signal stream open");
+        let (id, cursor): (String, u64) = signal.body().deserialize().expect("signal body");
+
+        assert_eq!((id.as_str(), cursor), ("0x2", 1));
+
+        server_task.abort();
+    }
+}
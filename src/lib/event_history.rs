@@ -5,9 +5,13 @@ use std::num::NonZeroUsize;
 use std::str::FromStr;
 
 use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 
 use crate::types::EventItem;
 
+const CHANGE_CHANNEL_CAPACITY: usize = 64;
+
 #[derive(Debug)]
 enum EventStatus<T> {
     Active(T),
@@ -15,6 +19,53 @@ enum EventStatus<T> {
     Deleted,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum HistoryChange<ID> {
+    Added {
+        id: ID,
+        cursor: usize,
+    },
+    /// Every entry of `id` was deleted as its window closed, or one entry of it was dropped
+    /// from the history, shifting the indices after it
+    Removed {
+        id: ID,
+    },
+    Deactivated {
+        id: ID,
+    },
+    Activated {
+        id: ID,
+    },
+    CursorMoved {
+        id: Option<ID>,
+        cursor: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryStatus {
+    Active,
+    Inactive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusCorrection<ID> {
+    pub id: ID,
+    pub from: EntryStatus,
+    /// `None` when the entry was deleted
+    pub to: Option<EntryStatus>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HistoryEntry<'a, T> {
+    pub index: usize,
+    pub status: EntryStatus,
+    pub current: bool,
+    pub event: &'a T,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CursorRealignment {
     PreviousActive(usize),
@@ -36,9 +87,19 @@ impl<T> EventStatus<T> {
             EventStatus::Deleted => None,
         }
     }
+
+    /// Event of an active entry, or of an inactive one when `include_inactive` is set
+    fn reachable_event(&self, include_inactive: bool) -> Option<&T> {
+        match self {
+            EventStatus::Active(t) => Some(t),
+            EventStatus::Inactive(t) if include_inactive => Some(t),
+            EventStatus::Inactive(_) | EventStatus::Deleted => None,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "usize")]
 pub struct HistorySize(NonZeroUsize);
 
 impl HistorySize {
@@ -86,6 +147,7 @@ pub struct EventHistory<T: EventItem> {
     cursor: usize,
     events: VecDeque<EventStatus<T>>,
     ignored_events: HashSet<T::ID>,
+    changes: broadcast::Sender<HistoryChange<T::ID>>,
 }
 
 impl<T: EventItem> EventHistory<T> {
@@ -97,9 +159,69 @@ impl<T: EventItem> EventHistory<T> {
             cursor: 0,
             events: VecDeque::new(),
             ignored_events: HashSet::default(),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<HistoryChange<T::ID>> {
+        self.changes.subscribe()
+    }
+
+    fn emit(&self, change: HistoryChange<T::ID>) {
+        // Sending only fails when there are no subscribers, which is the common case
+        let _ = self.changes.send(change);
+    }
+
+    fn emit_added(&self) {
+        if let Some(event) = self
+            .events
+            .get(self.cursor)
+            .and_then(EventStatus::get_event)
+        {
+            self.emit(HistoryChange::Added {
+                id: event.get_id().clone(),
+                cursor: self.cursor,
+            });
+        }
+    }
+
+    /// Announces entries leaving the history. Deleted entries were announced when their window
+    /// closed.
+    fn emit_dropped<'a>(&self, dropped: impl IntoIterator<Item = &'a EventStatus<T>>)
+    where
+        T: 'a,
+    {
+        for event in dropped {
+            if let Some(event) = event.get_event() {
+                self.emit(HistoryChange::Removed {
+                    id: event.get_id().clone(),
+                });
+            }
         }
     }
 
+    fn emit_cursor_moved(&self) {
+        self.emit(HistoryChange::CursorMoved {
+            id: self
+                .events
+                .get(self.cursor)
+                .and_then(EventStatus::get_event)
+                .map(T::get_id)
+                .cloned(),
+            cursor: self.cursor,
+        });
+    }
+
+    fn contains_matching<F>(&self, id: &T::ID, predicate: F) -> bool
+    where
+        F: Fn(&EventStatus<T>) -> bool,
+    {
+        self.events
+            .iter()
+            .any(|event| predicate(event) && event.get_event().is_some_and(|e| e.get_id() == id))
+    }
+
     pub fn bootstrap(init: T, max_size: HistorySize) -> Self {
         info!(
             "Bootstrapping event history with max_size: {}",
@@ -111,9 +233,24 @@ impl<T: EventItem> EventHistory<T> {
     }
 
     fn next_active_idx(&self, current: usize, current_id: Option<&T::ID>) -> Option<usize> {
+        self.next_matching_idx(current, current_id, false, |_| true)
+    }
+
+    fn next_matching_idx<P>(
+        &self,
+        current: usize,
+        current_id: Option<&T::ID>,
+        include_inactive: bool,
+        predicate: P,
+    ) -> Option<usize>
+    where
+        P: Fn(&T) -> bool,
+    {
         let mut idx = current + 1;
         while let Some(event) = self.events.get(idx) {
-            if let EventStatus::Active(event) = event {
+            if let Some(event) = event.reachable_event(include_inactive)
+                && predicate(event)
+            {
                 match current_id {
                     Some(id) if id != event.get_id() => {
                         return Some(idx);
@@ -127,10 +264,25 @@ impl<T: EventItem> EventHistory<T> {
     }
 
     fn prev_active_idx(&self, current: usize, current_id: Option<&T::ID>) -> Option<usize> {
+        self.prev_matching_idx(current, current_id, false, |_| true)
+    }
+
+    fn prev_matching_idx<P>(
+        &self,
+        current: usize,
+        current_id: Option<&T::ID>,
+        include_inactive: bool,
+        predicate: P,
+    ) -> Option<usize>
+    where
+        P: Fn(&T) -> bool,
+    {
         let mut idx = current;
         while idx > 0 {
             idx -= 1;
-            if let Some(EventStatus::Active(event)) = self.events.get(idx) {
+            if let Some(event) = self.events[idx].reachable_event(include_inactive)
+                && predicate(event)
+            {
                 match current_id {
                     Some(id) if id != event.get_id() => {
                         return Some(idx);
@@ -177,18 +329,20 @@ impl<T: EventItem> EventHistory<T> {
             return None;
         }
 
-        if let Some(prev_active_idx) =
-            self.prev_active_idx(self.cursor, id_at_updated_cursor.as_ref())
-        {
+        Some(self.realign_cursor(id_at_updated_cursor.as_ref()))
+    }
+
+    /// Moves the cursor off an entry which is no longer active, preferring the closest older
+    /// active entry that belongs to a different id than `id_at_cursor`
+    fn realign_cursor(&mut self, id_at_cursor: Option<&T::ID>) -> CursorRealignment {
+        if let Some(prev_active_idx) = self.prev_active_idx(self.cursor, id_at_cursor) {
             self.cursor = prev_active_idx;
-            return Some(CursorRealignment::PreviousActive(prev_active_idx));
+            return CursorRealignment::PreviousActive(prev_active_idx);
         }
 
-        if let Some(next_active_idx) =
-            self.next_active_idx(self.cursor, id_at_updated_cursor.as_ref())
-        {
+        if let Some(next_active_idx) = self.next_active_idx(self.cursor, id_at_cursor) {
             self.cursor = next_active_idx;
-            return Some(CursorRealignment::NextActive(next_active_idx));
+            return CursorRealignment::NextActive(next_active_idx);
         }
 
         if let Some((last_inactive_idx, _)) = self
@@ -199,11 +353,11 @@ impl<T: EventItem> EventHistory<T> {
             .find(|(_, status)| matches!(status, EventStatus::Inactive(_)))
         {
             self.cursor = last_inactive_idx;
-            return Some(CursorRealignment::LastInactive(last_inactive_idx));
+            return CursorRealignment::LastInactive(last_inactive_idx);
         }
 
         self.cursor = 0;
-        Some(CursorRealignment::ResetToStart(0))
+        CursorRealignment::ResetToStart(0)
     }
 
     #[allow(clippy::missing_panics_doc)]
@@ -213,19 +367,77 @@ impl<T: EventItem> EventHistory<T> {
         current_event
     }
 
+    /// Walks `steps` entries away from the cursor with `step`, stopping early when the
+    /// history runs out. Returns `None` when not even a single step could be taken.
+    fn step_active_idx<F>(&self, steps: NonZeroUsize, step: F) -> Option<usize>
+    where
+        F: Fn(&Self, usize, Option<&T::ID>) -> Option<usize>,
+    {
+        let mut position = self.cursor;
+        let mut moved = false;
+
+        for _ in 0..steps.get() {
+            let current_id = self
+                .events
+                .get(position)
+                .and_then(EventStatus::get_event)
+                .map(T::get_id);
+
+            let Some(next_position) = step(self, position, current_id) else {
+                break;
+            };
+
+            position = next_position;
+            moved = true;
+        }
+
+        moved.then_some(position)
+    }
+
     pub fn forward(&mut self) -> Option<&T> {
-        let current_id = self
-            .events
-            .get(self.cursor)
-            .and_then(EventStatus::get_event)
-            .map(T::get_id);
+        self.forward_by(NonZeroUsize::MIN)
+    }
+
+    pub fn forward_by(&mut self, steps: NonZeroUsize) -> Option<&T> {
+        self.forward_matching(steps, |_| true)
+    }
+
+    /// Like [`Self::forward_by`], only counting and stopping on events matching `predicate`
+    pub fn forward_matching<P>(&mut self, steps: NonZeroUsize, predicate: P) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        self.forward_reaching(steps, false, predicate)
+    }
+
+    /// Like [`Self::forward_matching`], also stopping on inactive events
+    pub fn forward_matching_inactive<P>(&mut self, steps: NonZeroUsize, predicate: P) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        self.forward_reaching(steps, true, predicate)
+    }
+
+    fn forward_reaching<P>(
+        &mut self,
+        steps: NonZeroUsize,
+        include_inactive: bool,
+        predicate: P,
+    ) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let new_cursor_position =
+            self.step_active_idx(steps, |history, position, current_id| {
+                history.next_matching_idx(position, current_id, include_inactive, &predicate)
+            })?;
 
-        let new_cursor_position = self.next_active_idx(self.cursor, current_id)?;
         self.cursor = new_cursor_position;
+        self.emit_cursor_moved();
         let current_event: &T = self.events[new_cursor_position].get_event()?;
         self.ignored_events.insert(current_event.get_id().clone());
         debug!(
-            "Forward invoked; cursor moved to {new_cursor_position} with id {}; {} inserted into ignore set.",
+            "Forward invoked with {steps} steps; cursor moved to {new_cursor_position} with id {}; {} inserted into ignore set.",
             current_event.get_id(),
             current_event.get_id(),
         );
@@ -233,28 +445,125 @@ impl<T: EventItem> EventHistory<T> {
     }
 
     pub fn backward(&mut self) -> Option<&T> {
-        let current_id = self
-            .events
-            .get(self.cursor)
-            .and_then(EventStatus::get_event)
-            .map(T::get_id);
+        self.backward_by(NonZeroUsize::MIN)
+    }
 
-        let new_cursor_position = self.prev_active_idx(self.cursor, current_id)?;
+    pub fn backward_by(&mut self, steps: NonZeroUsize) -> Option<&T> {
+        self.backward_matching(steps, |_| true)
+    }
+
+    /// Like [`Self::backward_by`], only counting and stopping on events matching `predicate`
+    pub fn backward_matching<P>(&mut self, steps: NonZeroUsize, predicate: P) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        self.backward_reaching(steps, false, predicate)
+    }
+
+    /// Like [`Self::backward_matching`], also stopping on inactive events
+    pub fn backward_matching_inactive<P>(&mut self, steps: NonZeroUsize, predicate: P) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        self.backward_reaching(steps, true, predicate)
+    }
+
+    fn backward_reaching<P>(
+        &mut self,
+        steps: NonZeroUsize,
+        include_inactive: bool,
+        predicate: P,
+    ) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let new_cursor_position =
+            self.step_active_idx(steps, |history, position, current_id| {
+                history.prev_matching_idx(position, current_id, include_inactive, &predicate)
+            })?;
 
         self.cursor = new_cursor_position;
+        self.emit_cursor_moved();
         let current_event: &T = self.events[new_cursor_position].get_event()?;
         self.ignored_events.insert(current_event.get_id().clone());
         debug!(
-            "Backward invoked; cursor moved to {new_cursor_position} with id {}; {} inserted into ignore set.",
+            "Backward invoked with {steps} steps; cursor moved to {new_cursor_position} with id {}; {} inserted into ignore set.",
             current_event.get_id(),
             current_event.get_id(),
         );
         Some(current_event)
     }
 
+    pub fn goto(&mut self, idx: usize) -> Option<&T> {
+        let Some(EventStatus::Active(_)) = self.events.get(idx) else {
+            debug!("Goto invoked with {idx}; no active event at that position.");
+            return None;
+        };
+
+        self.cursor = idx;
+        self.emit_cursor_moved();
+        let current_event: &T = self.events[idx].get_event()?;
+        self.ignored_events.insert(current_event.get_id().clone());
+        debug!(
+            "Goto invoked; cursor moved to {idx} with id {}; {} inserted into ignore set.",
+            current_event.get_id(),
+            current_event.get_id(),
+        );
+        Some(current_event)
+    }
+
+    /// Latest active entry of every distinct event matching `predicate` with its index, most
+    /// recent first
+    pub fn most_recent_distinct<P>(&self, predicate: P) -> Vec<(usize, &T)>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let mut seen = HashSet::new();
+        self.events
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(idx, event)| match event {
+                EventStatus::Active(event) if predicate(event) && seen.insert(event.get_id()) => {
+                    Some((idx, event))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Event at the cursor, unless it was deleted
+    pub fn cursor_event(&self) -> Option<&T> {
+        self.events
+            .get(self.cursor)
+            .and_then(EventStatus::get_event)
+    }
+
+    /// Active and inactive entries, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = HistoryEntry<'_, T>> {
+        self.events.iter().enumerate().filter_map(|(index, event)| {
+            let (status, event) = match event {
+                EventStatus::Active(event) => (EntryStatus::Active, event),
+                EventStatus::Inactive(event) => (EntryStatus::Inactive, event),
+                EventStatus::Deleted => return None,
+            };
+
+            Some(HistoryEntry {
+                index,
+                status,
+                current: index == self.cursor,
+                event,
+            })
+        })
+    }
+
     pub fn remove(&mut self, id: &T::ID) {
         info!("Removing event with id {id}");
+        if self.contains_matching(id, |_| true) {
+            self.emit(HistoryChange::Removed { id: id.clone() });
+        }
         if let Some(realignment) = self.update_matching_events(id, |_| None) {
+            self.emit_cursor_moved();
             match realignment {
                 CursorRealignment::PreviousActive(idx) => {
                     debug!(
@@ -280,14 +589,33 @@ impl<T: EventItem> EventHistory<T> {
         }
     }
 
+    /// Applies `update` to every active and inactive entry with `id`, without changing statuses
+    /// or the cursor
+    pub fn update<F>(&mut self, id: &T::ID, mut update: F)
+    where
+        F: FnMut(&mut T),
+    {
+        for event in &mut self.events {
+            if let EventStatus::Active(item) | EventStatus::Inactive(item) = event
+                && item.get_id() == id
+            {
+                update(item);
+            }
+        }
+    }
+
     pub fn deactivate(&mut self, id: &T::ID) {
         info!("Deactivating event with id {id}");
+        if self.contains_matching(id, |event| matches!(event, EventStatus::Active(_))) {
+            self.emit(HistoryChange::Deactivated { id: id.clone() });
+        }
         if let Some(realignment) =
             self.update_matching_events(id, |previous_event| match previous_event {
                 EventStatus::Active(t) => Some(EventStatus::Inactive(t)),
                 _ => None,
             })
         {
+            self.emit_cursor_moved();
             match realignment {
                 CursorRealignment::PreviousActive(idx) => {
                     debug!(
@@ -315,6 +643,9 @@ impl<T: EventItem> EventHistory<T> {
 
     pub fn activate(&mut self, id: &T::ID) {
         info!("Activating event with id {id}");
+        if self.contains_matching(id, |event| matches!(event, EventStatus::Inactive(_))) {
+            self.emit(HistoryChange::Activated { id: id.clone() });
+        }
         for event in &mut self.events {
             let event_matches_id = event
                 .get_event()
@@ -328,6 +659,104 @@ impl<T: EventItem> EventHistory<T> {
         }
     }
 
+    /// Re-evaluates every active and inactive entry with `resolve`, which may update the event
+    /// in place and returns the status the entry should have, or `None` if it should be
+    /// deleted. Returns one correction per id whose status changed.
+    pub fn reconcile<F>(&mut self, mut resolve: F) -> Vec<StatusCorrection<T::ID>>
+    where
+        F: FnMut(&mut T) -> Option<EntryStatus>,
+    {
+        let mut corrections: Vec<StatusCorrection<T::ID>> = Vec::new();
+        let mut id_at_changed_cursor = None;
+
+        for (idx, event) in self.events.iter_mut().enumerate() {
+            let (from, mut item) = match mem::replace(event, EventStatus::Deleted) {
+                EventStatus::Active(t) => (EntryStatus::Active, t),
+                EventStatus::Inactive(t) => (EntryStatus::Inactive, t),
+                EventStatus::Deleted => continue,
+            };
+
+            let to = resolve(&mut item);
+
+            if to != Some(from) {
+                if idx == self.cursor {
+                    id_at_changed_cursor = Some(item.get_id().clone());
+                }
+
+                if !corrections
+                    .iter()
+                    .any(|correction| &correction.id == item.get_id())
+                {
+                    corrections.push(StatusCorrection {
+                        id: item.get_id().clone(),
+                        from,
+                        to,
+                    });
+                }
+            }
+
+            *event = match to {
+                Some(EntryStatus::Active) => EventStatus::Active(item),
+                Some(EntryStatus::Inactive) => EventStatus::Inactive(item),
+                None => EventStatus::Deleted,
+            };
+        }
+
+        for correction in &corrections {
+            let id = correction.id.clone();
+            self.emit(match correction.to {
+                Some(EntryStatus::Active) => HistoryChange::Activated { id },
+                Some(EntryStatus::Inactive) => HistoryChange::Deactivated { id },
+                None => HistoryChange::Removed { id },
+            });
+        }
+
+        let cursor_inactive = !matches!(self.events.get(self.cursor), Some(EventStatus::Active(_)));
+
+        if let Some(id) = id_at_changed_cursor
+            && cursor_inactive
+        {
+            let realignment = self.realign_cursor(Some(&id));
+            debug!("Cursor present in reconciled events, realigned with {realignment:?}");
+            self.emit_cursor_moved();
+        }
+
+        corrections
+    }
+
+    /// Changes the maximum number of entries, trimming the oldest entries when the history is
+    /// longer than `max_size`. A cursor on a trimmed entry moves to the oldest remaining active
+    /// entry.
+    pub fn resize(&mut self, max_size: HistorySize) {
+        if max_size.get() != self.max_size.get() {
+            info!(
+                "Resizing event history from max_size {} to {}",
+                self.max_size.get(),
+                max_size.get()
+            );
+        }
+        self.max_size = max_size;
+
+        let excess = self.events.len().saturating_sub(max_size.get());
+        if excess == 0 {
+            return;
+        }
+
+        let trimmed: Vec<EventStatus<T>> = self.events.drain(..excess).collect();
+        self.emit_dropped(&trimmed);
+
+        self.cursor = match self.cursor.checked_sub(excess) {
+            Some(cursor) => cursor,
+            None => self
+                .events
+                .iter()
+                .position(|event| matches!(event, EventStatus::Active(_)))
+                .unwrap_or(0),
+        };
+        debug!("Trimmed {excess} entries; cursor moved to {}", self.cursor);
+        self.emit_cursor_moved();
+    }
+
     pub fn add(&mut self, item: T) -> Option<&T> {
         if self.ignored_events.contains(item.get_id()) {
             debug!(
@@ -354,13 +783,15 @@ impl<T: EventItem> EventHistory<T> {
         if self.events.is_empty() {
             self.events.push_back(active_item);
             self.cursor = 0;
+            self.emit_added();
             return self.events.back().and_then(EventStatus::get_event);
         }
 
         let buffer_full = self.events.len() >= self.max_size.get();
 
         if buffer_full {
-            self.events.pop_front();
+            let evicted = self.events.pop_front();
+            self.emit_dropped(&evicted);
             if self.cursor > 0 {
                 self.cursor -= 1;
             }
@@ -369,15 +800,13 @@ impl<T: EventItem> EventHistory<T> {
         let cursor_detached = !self.events.is_empty() && self.cursor + 1 != self.events.len();
 
         if cursor_detached {
-            let mut idx = self.events.len() - 1;
-            while idx > self.cursor {
-                self.events.pop_back();
-                idx -= 1;
-            }
+            let truncated = self.events.split_off(self.cursor + 1);
+            self.emit_dropped(&truncated);
         }
 
         self.events.push_back(active_item);
         self.cursor = self.events.len() - 1;
+        self.emit_added();
 
         self.events[self.events.len() - 1].get_event()
     }
@@ -386,8 +815,14 @@ impl<T: EventItem> EventHistory<T> {
 #[cfg(test)]
 mod tests {
     use std::collections::{HashSet, VecDeque};
+    use std::num::NonZeroUsize;
 
-    use super::{EventHistory, EventItem, EventStatus, HistorySize};
+    use tokio::sync::broadcast;
+
+    use super::{
+        CHANGE_CHANNEL_CAPACITY, EntryStatus, EventHistory, EventItem, EventStatus, HistoryChange,
+        HistoryEntry, HistorySize, StatusCorrection,
+    };
 
     impl EventItem for i32 {
         type ID = i32;
@@ -407,6 +842,7 @@ mod tests {
             cursor,
             events: VecDeque::from(events),
             ignored_events: HashSet::new(),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
         }
     }
 
@@ -603,6 +1039,75 @@ mod tests {
         assert_eq!(history.cursor, 3);
     }
 
+    #[test]
+    fn matching_traversal_only_counts_matching_events() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(2),
+                EventStatus::Active(4),
+                EventStatus::Inactive(6),
+                EventStatus::Active(5),
+                EventStatus::Active(8),
+                EventStatus::Active(7),
+            ],
+            5,
+        );
+        let even = |event: &i32| event % 2 == 0;
+
+        assert!(matches!(
+            history.backward_matching(NonZeroUsize::MIN, even),
+            Some(&8)
+        ));
+        assert!(matches!(
+            history.backward_matching(NonZeroUsize::new(2).expect("non-zero"), even),
+            Some(&2)
+        ));
+        assert_eq!(history.cursor, 0);
+
+        assert!(matches!(
+            history.forward_matching(NonZeroUsize::MIN, even),
+            Some(&4)
+        ));
+        assert!(
+            history
+                .forward_matching(NonZeroUsize::MIN, |event| *event > 8)
+                .is_none()
+        );
+        assert_eq!(history.cursor, 1);
+    }
+
+    #[test]
+    fn inactive_traversal_stops_on_inactive_events() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Inactive(1),
+                EventStatus::Active(2),
+                EventStatus::Deleted,
+                EventStatus::Inactive(3),
+                EventStatus::Active(4),
+            ],
+            4,
+        );
+
+        assert!(matches!(
+            history.backward_matching_inactive(NonZeroUsize::MIN, |_| true),
+            Some(&3)
+        ));
+        assert!(matches!(
+            history.backward_matching_inactive(NonZeroUsize::new(2).expect("non-zero"), |_| true),
+            Some(&1)
+        ));
+        assert!(matches!(
+            history.forward_matching_inactive(NonZeroUsize::MIN, |event| *event > 2),
+            Some(&3)
+        ));
+        assert!(matches!(
+            history.backward_matching(NonZeroUsize::MIN, |_| true),
+            Some(&2)
+        ));
+        assert_eq!(history.cursor, 1);
+    }
+
     #[test]
     fn backward_moves_cursor_when_not_at_start() {
         let mut history = new_history(4);
@@ -726,6 +1231,169 @@ mod tests {
         assert_eq!(history.cursor, 0);
     }
 
+    #[test]
+    fn forward_by_moves_multiple_active_entries() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Active(2),
+                EventStatus::Inactive(3),
+                EventStatus::Active(4),
+                EventStatus::Active(5),
+            ],
+            0,
+        );
+
+        let result = history.forward_by(NonZeroUsize::new(3).unwrap());
+
+        assert!(matches!(result, Some(&5)));
+        assert_eq!(history.cursor, 4);
+        assert_eq!(history.ignored_events, HashSet::from([5]));
+    }
+
+    #[test]
+    fn forward_by_stops_at_last_active_entry() {
+        let mut history = manual_history(vec![EventStatus::Active(1), EventStatus::Active(2)], 0);
+
+        let result = history.forward_by(NonZeroUsize::new(5).unwrap());
+
+        assert!(matches!(result, Some(&2)));
+        assert_eq!(history.cursor, 1);
+    }
+
+    #[test]
+    fn backward_by_moves_multiple_active_entries() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Active(2),
+                EventStatus::Deleted,
+                EventStatus::Active(3),
+                EventStatus::Active(4),
+            ],
+            4,
+        );
+
+        let result = history.backward_by(NonZeroUsize::new(2).unwrap());
+
+        assert!(matches!(result, Some(&2)));
+        assert_eq!(history.cursor, 1);
+        assert_eq!(history.ignored_events, HashSet::from([2]));
+    }
+
+    #[test]
+    fn backward_by_does_not_move_at_start() {
+        let mut history = manual_history(vec![EventStatus::Active(1), EventStatus::Active(2)], 0);
+
+        let result = history.backward_by(NonZeroUsize::new(3).unwrap());
+
+        assert!(result.is_none());
+        assert_eq!(history.cursor, 0);
+    }
+
+    #[test]
+    fn goto_moves_cursor_to_active_entry() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Active(2),
+                EventStatus::Active(3),
+            ],
+            2,
+        );
+
+        let result = history.goto(0);
+
+        assert!(matches!(result, Some(&1)));
+        assert_eq!(history.cursor, 0);
+        assert!(history.ignored_events.contains(&1));
+    }
+
+    #[test]
+    fn goto_ignores_inactive_deleted_and_missing_entries() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Inactive(1),
+                EventStatus::Deleted,
+                EventStatus::Active(3),
+            ],
+            2,
+        );
+
+        assert!(history.goto(0).is_none());
+        assert!(history.goto(1).is_none());
+        assert!(history.goto(3).is_none());
+        assert_eq!(history.cursor, 2);
+    }
+
+    #[test]
+    fn most_recent_distinct_lists_latest_matching_entries_first() {
+        let history = manual_history(
+            vec![
+                EventStatus::Active(2),
+                EventStatus::Active(4),
+                EventStatus::Active(1),
+                EventStatus::Inactive(6),
+                EventStatus::Active(2),
+                EventStatus::Deleted,
+                EventStatus::Active(3),
+            ],
+            6,
+        );
+
+        assert_eq!(
+            history.most_recent_distinct(|event| event % 2 == 0),
+            vec![(4, &2), (1, &4)]
+        );
+        assert_eq!(
+            history
+                .most_recent_distinct(|_| true)
+                .into_iter()
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>(),
+            vec![6, 4, 2, 1]
+        );
+    }
+
+    #[test]
+    fn entries_skip_deleted_and_mark_cursor() {
+        let history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Deleted,
+                EventStatus::Inactive(3),
+                EventStatus::Active(4),
+            ],
+            3,
+        );
+
+        let entries: Vec<_> = history.entries().collect();
+
+        assert_eq!(
+            entries,
+            vec![
+                HistoryEntry {
+                    index: 0,
+                    status: EntryStatus::Active,
+                    current: false,
+                    event: &1,
+                },
+                HistoryEntry {
+                    index: 2,
+                    status: EntryStatus::Inactive,
+                    current: false,
+                    event: &3,
+                },
+                HistoryEntry {
+                    index: 3,
+                    status: EntryStatus::Active,
+                    current: true,
+                    event: &4,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn remove_deletes_single_event() {
         let mut history = manual_history(
@@ -981,4 +1649,292 @@ mod tests {
         assert!(matches!(history.events[3], EventStatus::Deleted));
         assert!(matches!(history.events[4], EventStatus::Active(3)));
     }
+
+    #[test]
+    fn update_changes_active_and_inactive_entries_in_place() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Inactive(2),
+                EventStatus::Active(2),
+                EventStatus::Active(3),
+            ],
+            3,
+        );
+
+        history.update(&2, |event| *event *= 10);
+
+        assert!(matches!(history.events[1], EventStatus::Inactive(20)));
+        assert!(matches!(history.events[2], EventStatus::Active(20)));
+        assert!(matches!(history.events[3], EventStatus::Active(3)));
+        assert_eq!(history.cursor, 3);
+    }
+
+    fn drain_changes(
+        receiver: &mut broadcast::Receiver<HistoryChange<i32>>,
+    ) -> Vec<HistoryChange<i32>> {
+        let mut changes = Vec::new();
+        while let Ok(change) = receiver.try_recv() {
+            changes.push(change);
+        }
+        changes
+    }
+
+    #[test]
+    fn resize_trims_oldest_entries_and_shifts_cursor() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Active(2),
+                EventStatus::Active(1),
+                EventStatus::Active(3),
+            ],
+            3,
+        );
+        let mut receiver = history.subscribe();
+
+        history.resize(HistorySize::try_from(2).expect("size must be non-zero"));
+
+        assert_eq!(history.events.len(), 2);
+        assert!(matches!(history.events[0], EventStatus::Active(1)));
+        assert!(matches!(history.events[1], EventStatus::Active(3)));
+        assert_eq!(history.cursor, 1);
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::Removed { id: 1 },
+                HistoryChange::Removed { id: 2 },
+                HistoryChange::CursorMoved {
+                    id: Some(3),
+                    cursor: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resize_moves_trimmed_cursor_to_oldest_active_entry() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Active(2),
+                EventStatus::Inactive(3),
+                EventStatus::Active(4),
+            ],
+            0,
+        );
+
+        history.resize(HistorySize::try_from(2).expect("size must be non-zero"));
+
+        assert_eq!(history.cursor, 1);
+    }
+
+    #[test]
+    fn growing_keeps_entries_and_later_adds_fill_new_capacity() {
+        let mut history = new_history(2);
+        history.add(1);
+        history.add(2);
+
+        history.resize(HistorySize::try_from(3).expect("size must be non-zero"));
+        history.add(3);
+
+        assert_eq!(history.events.len(), 3);
+        assert!(matches!(history.events[0], EventStatus::Active(1)));
+        assert!(matches!(history.events[2], EventStatus::Active(3)));
+    }
+
+    #[test]
+    fn add_emits_added_change() {
+        let mut history = new_history(3);
+        let mut receiver = history.subscribe();
+
+        history.add(1);
+        history.add(1);
+        history.add(2);
+
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::Added { id: 1, cursor: 0 },
+                HistoryChange::Added { id: 2, cursor: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn add_emits_removed_for_evicted_and_truncated_entries() {
+        let mut history = new_history(3);
+        history.add(1);
+        history.add(2);
+        history.add(3);
+        let mut receiver = history.subscribe();
+
+        history.add(4);
+        history.backward();
+        history.add(5);
+
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::Removed { id: 1 },
+                HistoryChange::Added { id: 4, cursor: 2 },
+                HistoryChange::CursorMoved {
+                    id: Some(3),
+                    cursor: 1
+                },
+                HistoryChange::Removed { id: 2 },
+                HistoryChange::Removed { id: 4 },
+                HistoryChange::Added { id: 5, cursor: 1 },
+            ]
+        );
+        assert_eq!(history.events.len(), 2);
+    }
+
+    #[test]
+    fn traversal_emits_cursor_moved_change() {
+        let mut history = new_history(3);
+        history.add(1);
+        history.add(2);
+        let mut receiver = history.subscribe();
+
+        history.backward();
+        history.backward();
+        history.forward();
+
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::CursorMoved {
+                    id: Some(1),
+                    cursor: 0
+                },
+                HistoryChange::CursorMoved {
+                    id: Some(2),
+                    cursor: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn remove_emits_removed_and_realigned_cursor_changes() {
+        let mut history = manual_history(vec![EventStatus::Active(1), EventStatus::Active(2)], 1);
+        let mut receiver = history.subscribe();
+
+        history.remove(&3);
+        history.remove(&2);
+
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::Removed { id: 2 },
+                HistoryChange::CursorMoved {
+                    id: Some(1),
+                    cursor: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn status_changes_emit_only_when_status_changes() {
+        let mut history = manual_history(vec![EventStatus::Active(1), EventStatus::Active(2)], 1);
+        let mut receiver = history.subscribe();
+
+        history.activate(&1);
+        history.deactivate(&1);
+        history.activate(&1);
+        history.activate(&1);
+
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::Deactivated { id: 1 },
+                HistoryChange::Activated { id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn reconcile_applies_resolved_statuses() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Inactive(2),
+                EventStatus::Active(3),
+                EventStatus::Deleted,
+                EventStatus::Active(1),
+            ],
+            2,
+        );
+
+        let corrections = history.reconcile(|event| match event {
+            1 => Some(EntryStatus::Inactive),
+            _ => Some(EntryStatus::Active),
+        });
+
+        assert_eq!(
+            corrections,
+            vec![
+                StatusCorrection {
+                    id: 1,
+                    from: EntryStatus::Active,
+                    to: Some(EntryStatus::Inactive),
+                },
+                StatusCorrection {
+                    id: 2,
+                    from: EntryStatus::Inactive,
+                    to: Some(EntryStatus::Active),
+                },
+            ]
+        );
+        assert!(matches!(history.events[0], EventStatus::Inactive(1)));
+        assert!(matches!(history.events[1], EventStatus::Active(2)));
+        assert!(matches!(history.events[2], EventStatus::Active(3)));
+        assert!(matches!(history.events[3], EventStatus::Deleted));
+        assert!(matches!(history.events[4], EventStatus::Inactive(1)));
+        assert_eq!(history.cursor, 2);
+    }
+
+    #[test]
+    fn reconcile_updates_events_in_place() {
+        let mut history = manual_history(vec![EventStatus::Active(1), EventStatus::Active(2)], 1);
+
+        let corrections = history.reconcile(|event| {
+            *event *= 10;
+            Some(EntryStatus::Active)
+        });
+
+        assert!(corrections.is_empty());
+        assert!(matches!(history.events[0], EventStatus::Active(10)));
+        assert!(matches!(history.events[1], EventStatus::Active(20)));
+    }
+
+    #[test]
+    fn reconcile_realigns_cursor_when_cursor_entry_is_deleted() {
+        let mut history = manual_history(
+            vec![
+                EventStatus::Active(1),
+                EventStatus::Active(2),
+                EventStatus::Active(3),
+            ],
+            1,
+        );
+        let mut receiver = history.subscribe();
+
+        history.reconcile(|event| (*event != 2).then_some(EntryStatus::Active));
+
+        assert!(matches!(history.events[1], EventStatus::Deleted));
+        assert_eq!(history.cursor, 0);
+        assert_eq!(
+            drain_changes(&mut receiver),
+            vec![
+                HistoryChange::Removed { id: 2 },
+                HistoryChange::CursorMoved {
+                    id: Some(1),
+                    cursor: 0
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,116 @@
+use clap::ValueEnum;
+use hyprland::{
+    data::{Client, Clients, Monitor, WorkspaceBasic},
+    dispatch::{Dispatch, DispatchType, WindowIdentifier, WorkspaceIdentifierWithSpecial},
+    shared::{Address, HyprData, HyprDataActive, HyprDataActiveOptional},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    focus_history::{FocusHistory, FocusMessage},
+    scope::shown_workspace,
+};
+
+const SPECIAL_WORKSPACE_PREFIX: &str = "special:";
+
+/// How a window reached by `focus next`/`prev`/`goto` is brought into focus
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FocusAction {
+    /// Focus the window, switching to its workspace
+    #[default]
+    Focus,
+    /// Move the window to the focused workspace and focus it
+    Bring,
+    /// Exchange workspaces with the focused window and focus it
+    Swap,
+    /// Move the window to the focused monitor, unless it's already there, and focus it
+    MoveToMonitor,
+}
+
+fn workspace_identifier(workspace: &WorkspaceBasic) -> WorkspaceIdentifierWithSpecial<'_> {
+    match workspace.name.strip_prefix(SPECIAL_WORKSPACE_PREFIX) {
+        Some(name) if workspace.id < 0 => WorkspaceIdentifierWithSpecial::Special(Some(name)),
+        _ => WorkspaceIdentifierWithSpecial::Id(workspace.id),
+    }
+}
+
+/// Moves `window` to `workspace` without following it. The move is announced to `focus_events`
+/// first, so it isn't recorded as a new history entry, and withdrawn should it fail.
+async fn move_window(
+    focus_events: &FocusHistory,
+    window: &Client,
+    workspace: &WorkspaceBasic,
+) -> hyprland::Result<()> {
+    if window.workspace.id == workspace.id {
+        return Ok(());
+    }
+
+    focus_events.send(FocusMessage::ActionMove {
+        address: window.address.to_string(),
+    });
+    let moved = Dispatch::call_async(DispatchType::MoveToWorkspaceSilent(
+        workspace_identifier(workspace),
+        Some(WindowIdentifier::Address(window.address.clone())),
+    ))
+    .await;
+    if moved.is_err() {
+        focus_events.send(FocusMessage::ActionMoveFailed {
+            address: window.address.to_string(),
+        });
+    }
+    moved
+}
+
+async fn focus_window(address: &Address) -> hyprland::Result<()> {
+    Dispatch::call_async(DispatchType::FocusWindow(WindowIdentifier::Address(
+        address.clone(),
+    )))
+    .await
+}
+
+impl FocusAction {
+    /// Brings the window at `address` into focus, after moving it or the focused window as the
+    /// action requires
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn perform(self, address: &str, focus_events: &FocusHistory) -> hyprland::Result<()> {
+        let address = Address::new(address);
+        if self == Self::Focus {
+            return focus_window(&address).await;
+        }
+
+        let Some(target) = Clients::get_async()
+            .await?
+            .into_iter()
+            .find(|client| client.address == address)
+        else {
+            return focus_window(&address).await;
+        };
+        let monitor = Monitor::get_active_async().await?;
+
+        match self {
+            Self::Focus => {}
+            Self::Bring => move_window(focus_events, &target, shown_workspace(&monitor)).await?,
+            Self::Swap => {
+                let focused = Client::get_active_async().await?;
+                let Some(focused) = focused.filter(|focused| focused.address != target.address)
+                else {
+                    return focus_window(&address).await;
+                };
+
+                // The target is focused before the other window leaves, so Hyprland doesn't
+                // fall back to focusing an unrelated window in between
+                move_window(focus_events, &target, &focused.workspace).await?;
+                focus_window(&address).await?;
+                return move_window(focus_events, &focused, &target.workspace).await;
+            }
+            Self::MoveToMonitor => {
+                if target.monitor != Some(monitor.id) {
+                    move_window(focus_events, &target, &monitor.active_workspace).await?;
+                }
+            }
+        }
+
+        focus_window(&address).await
+    }
+}
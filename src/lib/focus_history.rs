@@ -0,0 +1,1694 @@
+use std::{collections::HashSet, num::NonZeroUsize};
+
+use chrono::{Local, NaiveDateTime};
+use hyprland::shared::WorkspaceId;
+use log::{debug, error, info};
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use crate::{
+    event_history::{
+        EntryStatus, EventHistory, HistoryChange, HistoryEntry, HistorySize, StatusCorrection,
+    },
+    frecency,
+    hypr_utils::{WindowMonitorRequest, get_window_monitor_request, window_on_requested_monitor},
+    marks::{ListedMark, Marks},
+    monitor_selector::{MonitorSelector, TrackedMonitors},
+    scope::ScopeLocation,
+    socket::{ListedWindow, SocketInstruction},
+    types::{SortedDistinctVec, WindowEvent},
+    window_cache::WindowCache,
+    window_filter::{ValuePattern, WindowFilters},
+};
+
+/// Hyprland events and socket commands, processed by [`FocusHistoryActor`] in the order they
+/// were sent
+#[derive(Debug)]
+pub(crate) enum FocusMessage {
+    WindowOpened {
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    },
+    WindowClosed {
+        address: String,
+    },
+    WindowMoved {
+        address: String,
+        workspace: String,
+        workspace_id: WorkspaceId,
+        time: NaiveDateTime,
+    },
+    WindowTitleChanged {
+        address: String,
+        title: String,
+    },
+    WindowFloatingChanged {
+        address: String,
+        floating: bool,
+    },
+    WindowPinnedChanged {
+        address: String,
+        pinned: bool,
+    },
+    /// A focus action is about to move the window at `address`, which isn't recorded as focus
+    ActionMove {
+        address: String,
+    },
+    /// The focus action announced for the window at `address` failed to move it
+    ActionMoveFailed {
+        address: String,
+    },
+    /// The focused window entered or left fullscreen
+    FullscreenChanged {
+        fullscreen: bool,
+    },
+    WorkspaceMoved {
+        workspace: String,
+        monitor: String,
+    },
+    WorkspaceDeleted {
+        workspace: String,
+    },
+    MonitorsChanged,
+    ActiveWindowChanged {
+        address: String,
+        time: NaiveDateTime,
+    },
+    /// The focused window as queried from Hyprland, e.g. after reconnecting
+    FocusedWindow(WindowEvent),
+    Reconcile {
+        reply: oneshot::Sender<anyhow::Result<()>>,
+    },
+    Reconfigure {
+        history_size: HistorySize,
+        requested_monitors: SortedDistinctVec<MonitorSelector>,
+        window_filters: WindowFilters,
+        reply: oneshot::Sender<()>,
+    },
+    Navigate {
+        instruction: SocketInstruction,
+        reply: oneshot::Sender<Option<String>>,
+    },
+    List {
+        reply: oneshot::Sender<Vec<ListedWindow>>,
+    },
+    ListByFrecency {
+        reply: oneshot::Sender<Vec<ListedWindow>>,
+    },
+    Subscribe {
+        reply: oneshot::Sender<broadcast::Receiver<HistoryChange<String>>>,
+    },
+    SetMark {
+        mark: char,
+        address: String,
+    },
+    ListMarks {
+        reply: oneshot::Sender<Vec<ListedMark>>,
+    },
+    ClearMark {
+        mark: char,
+    },
+}
+
+/// Handle to the focus history owned by a [`FocusHistoryActor`]
+#[derive(Clone, Debug)]
+pub struct FocusHistory {
+    messages: mpsc::UnboundedSender<FocusMessage>,
+}
+
+impl FocusHistory {
+    /// Creates a handle along with the actor owning `event_history`, `window_cache`,
+    /// `tracked_monitors`, `window_filters` and `marks`, which has to be run for any message to
+    /// be processed
+    #[must_use]
+    pub fn new(
+        event_history: EventHistory<WindowEvent>,
+        window_cache: WindowCache,
+        tracked_monitors: TrackedMonitors,
+        window_filters: WindowFilters,
+        marks: Marks,
+    ) -> (Self, FocusHistoryActor) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        (
+            Self { messages: sender },
+            FocusHistoryActor {
+                messages: receiver,
+                event_history,
+                window_cache,
+                tracked_monitors,
+                window_filters,
+                active_window: None,
+                action_moves: HashSet::new(),
+                marks,
+            },
+        )
+    }
+
+    /// Queues `message` without waiting, so messages sent from synchronous code keep their order
+    pub(crate) fn send(&self, message: FocusMessage) {
+        if self.messages.send(message).is_err() {
+            error!("Focus history is no longer running; dropped message");
+        }
+    }
+
+    async fn request<R>(
+        &self,
+        message: impl FnOnce(oneshot::Sender<R>) -> FocusMessage,
+    ) -> anyhow::Result<R> {
+        let (reply, response) = oneshot::channel();
+        self.send(message(reply));
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Focus history is no longer running"))
+    }
+
+    pub(crate) async fn reconcile(&self) -> anyhow::Result<()> {
+        self.request(|reply| FocusMessage::Reconcile { reply })
+            .await?
+    }
+
+    /// Applies reloaded settings in place, keeping the history
+    pub(crate) async fn reconfigure(
+        &self,
+        history_size: HistorySize,
+        requested_monitors: SortedDistinctVec<MonitorSelector>,
+        window_filters: WindowFilters,
+    ) -> anyhow::Result<()> {
+        self.request(|reply| FocusMessage::Reconfigure {
+            history_size,
+            requested_monitors,
+            window_filters,
+            reply,
+        })
+        .await
+    }
+
+    pub(crate) async fn navigate(
+        &self,
+        instruction: SocketInstruction,
+    ) -> anyhow::Result<Option<String>> {
+        self.request(|reply| FocusMessage::Navigate { instruction, reply })
+            .await
+    }
+
+    pub(crate) async fn list(&self) -> anyhow::Result<Vec<ListedWindow>> {
+        self.request(|reply| FocusMessage::List { reply }).await
+    }
+
+    pub(crate) async fn list_by_frecency(&self) -> anyhow::Result<Vec<ListedWindow>> {
+        self.request(|reply| FocusMessage::ListByFrecency { reply })
+            .await
+    }
+
+    pub(crate) async fn subscribe(
+        &self,
+    ) -> anyhow::Result<broadcast::Receiver<HistoryChange<String>>> {
+        self.request(|reply| FocusMessage::Subscribe { reply })
+            .await
+    }
+
+    pub(crate) fn set_mark(&self, mark: char, address: String) {
+        self.send(FocusMessage::SetMark { mark, address });
+    }
+
+    pub(crate) fn clear_mark(&self, mark: char) {
+        self.send(FocusMessage::ClearMark { mark });
+    }
+
+    pub(crate) async fn list_marks(&self) -> anyhow::Result<Vec<ListedMark>> {
+        self.request(|reply| FocusMessage::ListMarks { reply })
+            .await
+    }
+}
+
+/// Sole owner of the focus history and window cache
+#[derive(Debug)]
+pub struct FocusHistoryActor {
+    messages: mpsc::UnboundedReceiver<FocusMessage>,
+    event_history: EventHistory<WindowEvent>,
+    window_cache: WindowCache,
+    tracked_monitors: TrackedMonitors,
+    window_filters: WindowFilters,
+    active_window: Option<String>,
+    /// Windows whose next move is made by a focus action
+    action_moves: HashSet<String>,
+    marks: Marks,
+}
+
+impl FocusHistoryActor {
+    /// Processes messages one at a time until every [`FocusHistory`] handle is dropped
+    pub async fn run(mut self) {
+        while let Some(message) = self.messages.recv().await {
+            debug!("Processing focus message: {message:?}");
+            self.handle(message).await;
+        }
+    }
+
+    async fn handle(&mut self, message: FocusMessage) {
+        match message {
+            FocusMessage::WindowOpened {
+                address,
+                workspace,
+                class,
+                title,
+            } => self.window_opened(address, workspace, class, title),
+            FocusMessage::WindowClosed { address } => self.window_closed(&address),
+            FocusMessage::WindowMoved {
+                address,
+                workspace,
+                workspace_id,
+                time,
+            } => {
+                self.window_moved(address, workspace, workspace_id, time)
+                    .await;
+            }
+            FocusMessage::ActionMove { address } => self.action_move(address),
+            FocusMessage::ActionMoveFailed { address } => self.action_move_failed(&address),
+            FocusMessage::WindowTitleChanged { address, title } => {
+                self.window_title_changed(&address, title);
+            }
+            FocusMessage::WindowFloatingChanged { address, floating } => {
+                self.window_floating_changed(&address, floating);
+            }
+            FocusMessage::WindowPinnedChanged { address, pinned } => {
+                self.window_pinned_changed(&address, pinned);
+            }
+            FocusMessage::FullscreenChanged { fullscreen } => self.fullscreen_changed(fullscreen),
+            FocusMessage::WorkspaceMoved { workspace, monitor } => {
+                self.workspace_moved(workspace, monitor);
+            }
+            FocusMessage::WorkspaceDeleted { workspace } => {
+                self.window_cache.workspace_removed(&workspace);
+            }
+            FocusMessage::MonitorsChanged => self.monitors_changed().await,
+            FocusMessage::ActiveWindowChanged { address, time } => {
+                self.active_window_changed(address, time).await;
+            }
+            FocusMessage::FocusedWindow(window_event) => self.focused_window_queried(window_event),
+            FocusMessage::Reconcile { reply } => {
+                let _ = reply.send(self.reconcile().await);
+            }
+            FocusMessage::Reconfigure {
+                history_size,
+                requested_monitors,
+                window_filters,
+                reply,
+            } => {
+                self.reconfigure(history_size, requested_monitors, window_filters)
+                    .await;
+                let _ = reply.send(());
+            }
+            FocusMessage::Navigate { instruction, reply } => {
+                let _ = reply.send(self.navigate(instruction));
+            }
+            FocusMessage::SetMark { mark, address } => self.set_mark(mark, address).await,
+            FocusMessage::ListMarks { reply } => {
+                let _ = reply.send(self.marks.list(&self.window_cache));
+            }
+            FocusMessage::ClearMark { mark } => {
+                if !self.marks.clear(mark).await {
+                    debug!("Mark '{mark}' isn't set");
+                }
+            }
+            FocusMessage::List { reply } => {
+                let _ = reply.send(self.list());
+            }
+            FocusMessage::ListByFrecency { reply } => {
+                let _ = reply.send(self.list_by_frecency(Local::now().naive_local()));
+            }
+            FocusMessage::Subscribe { reply } => {
+                let _ = reply.send(self.event_history.subscribe());
+            }
+        }
+    }
+
+    async fn set_mark(&mut self, mark: char, address: String) {
+        self.complete_window_properties(&address).await;
+        let properties = self
+            .window_cache
+            .window_properties(&address)
+            .cloned()
+            .unwrap_or_default();
+        self.marks.set(mark, address, &properties).await;
+    }
+
+    fn window_opened(&mut self, address: String, workspace: String, class: String, title: String) {
+        self.window_cache
+            .window_opened(address, workspace, class, title);
+        self.marks.resolve(&self.window_cache);
+    }
+
+    fn window_closed(&mut self, address: &str) {
+        self.window_cache.window_closed(address);
+        self.marks.window_closed(address);
+        self.action_moves.remove(address);
+        self.event_history.remove(&address.to_string());
+    }
+
+    async fn window_moved(
+        &mut self,
+        address: String,
+        workspace: String,
+        workspace_id: WorkspaceId,
+        time: NaiveDateTime,
+    ) {
+        self.window_cache
+            .window_moved(address.clone(), workspace, workspace_id);
+        self.sync_window_properties(&address);
+        let record = !self.action_moves.remove(&address);
+        self.reevaluate_moved_window(address, time, record).await;
+    }
+
+    /// Announces that the next move of the window at `address` is made by a focus action
+    fn action_move(&mut self, address: String) {
+        self.action_moves.insert(address);
+    }
+
+    fn action_move_failed(&mut self, address: &str) {
+        self.action_moves.remove(address);
+    }
+
+    fn window_title_changed(&mut self, address: &str, title: String) {
+        self.window_cache.window_title_changed(address, title);
+        self.window_properties_changed(address, "title change");
+    }
+
+    fn window_floating_changed(&mut self, address: &str, floating: bool) {
+        self.window_cache.window_floating_changed(address, floating);
+        self.window_properties_changed(address, "floating change");
+    }
+
+    fn window_pinned_changed(&mut self, address: &str, pinned: bool) {
+        self.window_cache.window_pinned_changed(address, pinned);
+        self.window_properties_changed(address, "pin change");
+    }
+
+    fn fullscreen_changed(&mut self, fullscreen: bool) {
+        // Hyprland doesn't say which window this is about, only that it's focused
+        if let Some(address) = self.active_window.clone() {
+            self.window_cache
+                .window_fullscreen_changed(&address, fullscreen);
+            self.window_properties_changed(&address, "fullscreen change");
+        }
+    }
+
+    fn workspace_moved(&mut self, workspace: String, monitor: String) {
+        self.window_cache.workspace_moved(workspace, monitor);
+        self.refresh_statuses("workspace move");
+    }
+
+    async fn monitors_changed(&mut self) {
+        // Monitor changes shuffle workspaces around without reliably emitting an event per
+        // workspace, so the cache is rebuilt from scratch
+        match self.window_cache.reseed().await {
+            Ok(()) => self.window_cache_reseeded(),
+            Err(err) => error!("Failed to reseed window cache: {err}"),
+        }
+        if let Err(err) = self.tracked_monitors.refresh().await {
+            error!("Failed to resolve tracked monitors: {err}");
+        }
+        self.refresh_statuses("monitor change");
+    }
+
+    /// Records the focused window as queried from Hyprland
+    fn focused_window_queried(&mut self, window_event: WindowEvent) {
+        self.active_window = Some(window_event.address.clone());
+        if window_on_requested_monitor(&window_event, &self.tracked_monitors)
+            && self.window_filters.accepts(&window_event.properties)
+        {
+            self.event_history.add(window_event);
+        }
+    }
+
+    /// Queries Hyprland for the properties of a window only known from its open event, which
+    /// doesn't report all of them
+    async fn complete_window_properties(&mut self, address: &str) {
+        if !self.window_cache.has_complete_properties(address)
+            && let Err(err) = self.window_cache.complete_window(address).await
+        {
+            error!("Failed to query hyprland clients: {err}");
+        }
+    }
+
+    fn window_event(&self, address: String, monitor: String, time: NaiveDateTime) -> WindowEvent {
+        WindowEvent {
+            properties: self
+                .window_cache
+                .window_properties(&address)
+                .cloned()
+                .unwrap_or_default(),
+            address,
+            monitor: Some(monitor),
+            time,
+        }
+    }
+
+    /// Copies the cached properties of the window at `address` into its history entries
+    fn sync_window_properties(&mut self, address: &str) {
+        if let Some(properties) = self.window_cache.window_properties(address) {
+            self.event_history
+                .update(&address.to_string(), |window_event| {
+                    window_event.properties.clone_from(properties);
+                });
+        }
+    }
+
+    fn window_properties_changed(&mut self, address: &str, reason: &str) {
+        self.sync_window_properties(address);
+        self.refresh_filtered_statuses(reason);
+    }
+
+    /// Re-evaluates a moved window, and records it as focused when it moved onto a tracked
+    /// monitor and `record` is set
+    async fn reevaluate_moved_window(
+        &mut self,
+        address: String,
+        time: NaiveDateTime,
+        record: bool,
+    ) {
+        match get_window_monitor_request(&mut self.window_cache, &address, &self.tracked_monitors)
+            .await
+        {
+            WindowMonitorRequest::Matching { window_monitor } => {
+                self.complete_window_properties(&address).await;
+                let window_event = self.window_event(address, window_monitor, time);
+
+                if self.window_filters.accepts(&window_event.properties) {
+                    self.event_history.activate(&window_event.address);
+                    if record {
+                        self.event_history.add(window_event);
+                    }
+                } else {
+                    self.event_history.deactivate(&window_event.address);
+                }
+            }
+            WindowMonitorRequest::NoMatch => {
+                self.event_history.deactivate(&address);
+            }
+            WindowMonitorRequest::AllRequested { window_monitor: _ } => {
+                // Active/Inactive windows aren't necessary if all monitors are tracked, but
+                // rules on the workspace may now match differently
+                self.refresh_filtered_statuses("window move");
+            }
+        }
+    }
+
+    async fn active_window_changed(&mut self, address: String, time: NaiveDateTime) {
+        self.active_window = Some(address.clone());
+        match get_window_monitor_request(&mut self.window_cache, &address, &self.tracked_monitors)
+            .await
+        {
+            WindowMonitorRequest::Matching {
+                window_monitor: monitor,
+            }
+            | WindowMonitorRequest::AllRequested {
+                window_monitor: monitor,
+            } => {
+                self.complete_window_properties(&address).await;
+                let window_event = self.window_event(address, monitor, time);
+
+                if !self.window_filters.accepts(&window_event.properties) {
+                    debug!(
+                        "Ignoring focus of {}, which is excluded by window rules",
+                        window_event.address
+                    );
+                    return;
+                }
+
+                if let Some(WindowEvent { address, time, .. }) =
+                    self.event_history.add(window_event)
+                {
+                    info!("Registered active window event with id {address} at {time}");
+                }
+            }
+            WindowMonitorRequest::NoMatch => {}
+        }
+    }
+
+    async fn reconfigure(
+        &mut self,
+        history_size: HistorySize,
+        requested_monitors: SortedDistinctVec<MonitorSelector>,
+        window_filters: WindowFilters,
+    ) {
+        self.event_history.resize(history_size);
+        self.window_filters = window_filters;
+
+        self.tracked_monitors = TrackedMonitors::new(requested_monitors);
+        if let Err(err) = self.tracked_monitors.refresh().await {
+            error!("Failed to resolve tracked monitors: {err}");
+        }
+        self.refresh_statuses("configuration reload");
+    }
+
+    /// Re-evaluates the window rules after a window property changed, when there are any
+    fn refresh_filtered_statuses(&mut self, reason: &str) {
+        if !self.window_filters.is_empty() {
+            self.refresh_statuses(reason);
+        }
+    }
+
+    /// Activates or deactivates every entry whose window moved onto or off the tracked monitors
+    /// along with its workspace, which Hyprland doesn't report as a move of the window itself,
+    /// or which started or stopped matching the window rules
+    fn refresh_statuses(&mut self, reason: &str) {
+        let window_cache = &self.window_cache;
+        let tracked_monitors = &self.tracked_monitors;
+        let window_filters = &self.window_filters;
+        let mut moved_windows = HashSet::new();
+
+        let corrections = self.event_history.reconcile(|window_event| {
+            Some(resolve_window_status(
+                window_cache,
+                tracked_monitors,
+                window_filters,
+                window_event,
+                &mut moved_windows,
+            ))
+        });
+
+        for StatusCorrection { id, from, to } in &corrections {
+            info!("Status of {id} changed from {from:?} to {to:?} after {reason}");
+        }
+    }
+
+    /// Corrects every history entry's monitor and Active/Inactive/Deleted status against a
+    /// fresh snapshot of Hyprland's windows, for when window events may have been missed
+    /// Unbinds the marks of windows a reseed no longer found, then reattaches unbound marks
+    fn window_cache_reseeded(&mut self) {
+        self.marks.unbind_missing(&self.window_cache);
+        self.marks.resolve(&self.window_cache);
+    }
+
+    async fn reconcile(&mut self) -> anyhow::Result<()> {
+        self.window_cache.reseed().await?;
+        self.reconcile_with_window_cache();
+        Ok(())
+    }
+
+    /// Brings the marks and the history in line with a freshly reseeded window cache
+    fn reconcile_with_window_cache(&mut self) {
+        self.window_cache_reseeded();
+
+        let window_cache = &self.window_cache;
+        let tracked_monitors = &self.tracked_monitors;
+        let window_filters = &self.window_filters;
+        let mut moved_windows = HashSet::new();
+
+        let corrections = self.event_history.reconcile(|window_event| {
+            if !window_cache.contains_window(&window_event.address) {
+                return None;
+            }
+
+            Some(resolve_window_status(
+                window_cache,
+                tracked_monitors,
+                window_filters,
+                window_event,
+                &mut moved_windows,
+            ))
+        });
+
+        for StatusCorrection { id, from, to } in &corrections {
+            info!("Reconciled status of {id} from {from:?} to {to:?}");
+        }
+
+        debug!(
+            "Reconciled focus history; {} status and {} monitor corrections",
+            corrections.len(),
+            moved_windows.len()
+        );
+    }
+
+    fn navigate(&mut self, instruction: SocketInstruction) -> Option<String> {
+        let history = &mut self.event_history;
+        let window_cache = &self.window_cache;
+        let window_filters = &self.window_filters;
+        let address = |window_event: &WindowEvent| window_event.address.clone();
+        match instruction {
+            SocketInstruction::Next {
+                count,
+                class,
+                scope,
+                include_inactive,
+                ..
+            } => {
+                let matches = |window_event: &WindowEvent| {
+                    traversal_matches(window_event, class.as_ref(), scope.as_ref(), window_cache)
+                };
+                if include_inactive {
+                    history.forward_matching_inactive(count, |window_event| {
+                        window_filters.accepts(&window_event.properties) && matches(window_event)
+                    })
+                } else {
+                    history.forward_matching(count, matches)
+                }
+                .map(address)
+            }
+            SocketInstruction::Prev {
+                count,
+                class,
+                scope,
+                include_inactive,
+                ..
+            } => {
+                let matches = |window_event: &WindowEvent| {
+                    traversal_matches(window_event, class.as_ref(), scope.as_ref(), window_cache)
+                };
+                if include_inactive {
+                    history.backward_matching_inactive(count, |window_event| {
+                        window_filters.accepts(&window_event.properties) && matches(window_event)
+                    })
+                } else {
+                    history.backward_matching(count, matches)
+                }
+                .map(address)
+            }
+            SocketInstruction::Goto { index, .. } => history.goto(index).map(address),
+            SocketInstruction::Jump { class, .. } => self.jump(&class),
+            SocketInstruction::Last { .. } => self.last(),
+            SocketInstruction::Frecent { rank, .. } => {
+                self.frecent(rank, Local::now().naive_local())
+            }
+            // The cursor stays, so the focus event the jump causes is recorded like any other
+            SocketInstruction::JumpToMark { mark, .. } => {
+                self.marks.address(mark).map(str::to_string)
+            }
+            SocketInstruction::List
+            | SocketInstruction::Subscribe
+            | SocketInstruction::Reload
+            | SocketInstruction::SetMark { .. }
+            | SocketInstruction::ListMarks
+            | SocketInstruction::ClearMark { .. }
+            | SocketInstruction::ListByFrecency => None,
+        }
+    }
+
+    /// Moves the cursor to the latest entry of the most recently focused window of `class`, or
+    /// of the next most recent one when the cursor is on a window of `class` already. Without
+    /// a match in the history, any cached window of `class` is picked so the cursor stays
+    fn jump(&mut self, class: &ValuePattern) -> Option<String> {
+        let history = &mut self.event_history;
+        let candidates = history
+            .most_recent_distinct(|window_event| class.matches(&window_event.properties.class));
+        let current = history
+            .cursor_event()
+            .map(|window_event| &window_event.address);
+        let position = candidates
+            .iter()
+            .position(|(_, window_event)| Some(&window_event.address) == current);
+
+        let Some((target, window_event)) = (match position {
+            Some(position) => candidates.get(position + 1).or(candidates.first()),
+            None => candidates.first(),
+        }) else {
+            // Windows that haven't been focused since the daemon started have no entries
+            return self
+                .window_cache
+                .windows()
+                .filter(|(_, properties)| class.matches(&properties.class))
+                .map(|(address, _)| address)
+                .min()
+                .map(str::to_string);
+        };
+
+        let (target, address) = (*target, window_event.address.clone());
+        self.goto_unless_current(target, address)
+    }
+
+    /// Moves the cursor to the latest entry of the most recently focused window other than the
+    /// one at the cursor, wherever the cursor is
+    fn last(&mut self) -> Option<String> {
+        let history = &mut self.event_history;
+        let current = history
+            .cursor_event()
+            .map(|window_event| window_event.address.clone());
+        let (target, _) = *history
+            .most_recent_distinct(|window_event| Some(&window_event.address) != current.as_ref())
+            .first()?;
+
+        history
+            .goto(target)
+            .map(|window_event| window_event.address.clone())
+    }
+
+    /// Moves the cursor to the latest entry of the window at `rank` by frecency, counting
+    /// active windows only
+    fn frecent(&mut self, rank: NonZeroUsize, now: NaiveDateTime) -> Option<String> {
+        let history = &mut self.event_history;
+        let ranked = frecency::rank(history.entries(), now)
+            .into_iter()
+            .filter(|ranked| ranked.entry.status == EntryStatus::Active)
+            .nth(rank.get() - 1)?;
+        let (target, address) = (ranked.entry.index, ranked.entry.event.address.clone());
+
+        self.goto_unless_current(target, address)
+    }
+
+    /// Moves the cursor to the entry at `target` of the window at `address`, unless the cursor
+    /// is on that window already. No focus event would follow to take its address back out of
+    /// the ignore set.
+    fn goto_unless_current(&mut self, target: usize, address: String) -> Option<String> {
+        let history = &mut self.event_history;
+        if history
+            .cursor_event()
+            .is_some_and(|window_event| window_event.address == address)
+        {
+            return Some(address);
+        }
+
+        history
+            .goto(target)
+            .map(|window_event| window_event.address.clone())
+    }
+
+    fn list(&self) -> Vec<ListedWindow> {
+        self.event_history
+            .entries()
+            .map(|entry| listed_window(&entry, None))
+            .collect()
+    }
+
+    /// Latest entry of every window, highest frecency first
+    fn list_by_frecency(&self, now: NaiveDateTime) -> Vec<ListedWindow> {
+        frecency::rank(self.event_history.entries(), now)
+            .iter()
+            .map(|ranked| listed_window(&ranked.entry, Some(ranked.score)))
+            .collect()
+    }
+}
+
+fn listed_window(entry: &HistoryEntry<'_, WindowEvent>, frecency: Option<f64>) -> ListedWindow {
+    ListedWindow {
+        index: entry.index,
+        address: entry.event.address.clone(),
+        monitor: entry.event.monitor.clone(),
+        time: entry.event.time,
+        properties: entry.event.properties.clone(),
+        status: entry.status,
+        current: entry.current,
+        frecency,
+    }
+}
+
+/// Whether traversal limited to `class` and `scope`, when given, may stop on the window of
+/// `window_event`. Scopes are checked against where the window is now, not where it was focused.
+fn traversal_matches(
+    window_event: &WindowEvent,
+    class: Option<&ValuePattern>,
+    scope: Option<&ScopeLocation>,
+    window_cache: &WindowCache,
+) -> bool {
+    class.is_none_or(|class| class.matches(&window_event.properties.class))
+        && scope.is_none_or(|scope| scope.contains(window_cache, &window_event.address))
+}
+
+/// Updates the monitor and properties of `window_event` from `window_cache` when it's known
+/// there, and returns whether the window is on a tracked monitor and passes the window rules.
+/// Windows whose monitor changed are collected in `moved_windows`.
+fn resolve_window_status(
+    window_cache: &WindowCache,
+    tracked_monitors: &TrackedMonitors,
+    window_filters: &WindowFilters,
+    window_event: &mut WindowEvent,
+    moved_windows: &mut HashSet<String>,
+) -> EntryStatus {
+    if let Some(monitor) = window_cache.window_monitor(&window_event.address)
+        && window_event.monitor.as_deref() != Some(monitor)
+    {
+        if moved_windows.insert(window_event.address.clone()) {
+            info!(
+                "Monitor of {} changed from {:?} to {monitor:?}",
+                window_event.address, window_event.monitor
+            );
+        }
+        window_event.monitor = Some(monitor.to_string());
+    }
+
+    if let Some(properties) = window_cache.window_properties(&window_event.address) {
+        window_event.properties.clone_from(properties);
+    }
+
+    if window_on_requested_monitor(window_event, tracked_monitors)
+        && window_filters.accepts(&window_event.properties)
+    {
+        EntryStatus::Active
+    } else {
+        EntryStatus::Inactive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use chrono::NaiveDateTime;
+
+    use super::{FocusHistory, FocusHistoryActor, FocusMessage, traversal_matches};
+    use crate::{
+        event_history::{EntryStatus, EventHistory, HistorySize},
+        focus_action::FocusAction,
+        marks::Marks,
+        monitor_selector::TrackedMonitors,
+        scope::ScopeLocation,
+        socket::SocketInstruction,
+        types::{SortedDistinctVec, WindowEvent},
+        window_cache::{WindowCache, WindowProperties},
+        window_filter::{ValuePattern, WindowFilters},
+    };
+
+    fn spawn_focus_history(requested_monitors: &[&str]) -> FocusHistory {
+        spawn_filtered_focus_history(requested_monitors, WindowFilters::default())
+    }
+
+    fn spawn_filtered_focus_history(
+        requested_monitors: &[&str],
+        window_filters: WindowFilters,
+    ) -> FocusHistory {
+        let mut window_cache = WindowCache::default();
+        window_cache.workspace_moved("1".to_string(), "DP-1".to_string());
+        window_cache.workspace_moved("2".to_string(), "HDMI-A-1".to_string());
+
+        let selectors = requested_monitors
+            .iter()
+            .map(|selector| selector.parse().expect("valid selector"))
+            .collect();
+        let mut tracked_monitors = TrackedMonitors::new(SortedDistinctVec::new(selectors));
+        tracked_monitors.resolve([("DP-1", "Dell U2720Q"), ("HDMI-A-1", "LG 27GL850")]);
+
+        let (focus_history, actor) = FocusHistory::new(
+            EventHistory::new(HistorySize::default()),
+            window_cache,
+            tracked_monitors,
+            window_filters,
+            Marks::default(),
+        );
+        tokio::spawn(actor.run());
+        focus_history
+    }
+
+    fn open_and_focus(focus_history: &FocusHistory, address: &str, workspace: &str) {
+        open_and_focus_class(focus_history, address, workspace, "kitty");
+    }
+
+    fn open_and_focus_class(
+        focus_history: &FocusHistory,
+        address: &str,
+        workspace: &str,
+        class: &str,
+    ) {
+        focus_history.send(FocusMessage::WindowOpened {
+            address: address.to_string(),
+            workspace: workspace.to_string(),
+            class: class.to_string(),
+            title: format!("window {address}"),
+        });
+        focus_history.send(FocusMessage::ActiveWindowChanged {
+            address: address.to_string(),
+            time: NaiveDateTime::default(),
+        });
+    }
+
+    async fn listed_addresses(focus_history: &FocusHistory) -> Vec<String> {
+        focus_history
+            .list()
+            .await
+            .expect("focus history is running")
+            .into_iter()
+            .map(|window| window.address)
+            .collect()
+    }
+
+    fn window_event(address: &str, class: &str) -> WindowEvent {
+        WindowEvent {
+            address: address.to_string(),
+            monitor: Some("DP-1".to_string()),
+            time: NaiveDateTime::default(),
+            properties: WindowProperties {
+                class: class.to_string(),
+                ..WindowProperties::default()
+            },
+        }
+    }
+
+    /// An actor over the `(address, class)` windows focused in order, for checking how it picks
+    /// windows without going through messages
+    fn history_actor(windows: &[(&str, &str)]) -> FocusHistoryActor {
+        let mut history = EventHistory::new(HistorySize::default());
+        for (address, class) in windows {
+            history.add(window_event(address, class));
+        }
+        let (_, actor) = FocusHistory::new(
+            history,
+            WindowCache::default(),
+            TrackedMonitors::new(SortedDistinctVec::new(Vec::new())),
+            WindowFilters::default(),
+            Marks::default(),
+        );
+        actor
+    }
+
+    fn class(source: &str) -> ValuePattern {
+        source.parse().expect("valid class pattern")
+    }
+
+    #[test]
+    fn traversal_matches_class_patterns() {
+        let window_cache = WindowCache::default();
+        let firefox = window_event("0x1", "firefox");
+        let matches =
+            |class: Option<&ValuePattern>| traversal_matches(&firefox, class, None, &window_cache);
+
+        assert!(matches(None));
+        assert!(matches(Some(&class("firefox"))));
+        assert!(matches(Some(&class("re:^(firefox|chromium)$"))));
+        assert!(!matches(Some(&class("kitty"))));
+    }
+
+    #[test]
+    fn traversal_matches_scopes_where_the_window_is_now() {
+        let mut window_cache = WindowCache::default();
+        window_cache.workspace_moved("1".to_string(), "DP-1".to_string());
+        window_cache.workspace_moved("2".to_string(), "HDMI-A-1".to_string());
+        window_cache.window_opened(
+            "0x1".to_string(),
+            "1".to_string(),
+            "kitty".to_string(),
+            "window 0x1".to_string(),
+        );
+        window_cache.window_moved("0x1".to_string(), "2".to_string(), 2);
+        // Focused while it was on workspace 1 of DP-1
+        let window = window_event("0x1", "kitty");
+        let matches =
+            |scope: &ScopeLocation| traversal_matches(&window, None, Some(scope), &window_cache);
+
+        assert!(!matches(&ScopeLocation::Workspaces(vec!["1".to_string()])));
+        assert!(matches(&ScopeLocation::Workspaces(vec!["2".to_string()])));
+        assert!(matches(&ScopeLocation::Monitor("HDMI-A-1".to_string())));
+        assert!(!traversal_matches(
+            &window,
+            Some(&class("firefox")),
+            Some(&ScopeLocation::Monitor("HDMI-A-1".to_string())),
+            &window_cache
+        ));
+    }
+
+    #[test]
+    fn jump_picks_the_next_most_recent_window_when_one_is_focused() {
+        let mut actor = history_actor(&[
+            ("0x1", "kitty"),
+            ("0x2", "firefox"),
+            ("0x3", "kitty"),
+            ("0x4", "firefox"),
+            ("0x1", "kitty"),
+        ]);
+
+        assert_eq!(actor.jump(&class("firefox")), Some("0x4".to_string()));
+        assert_eq!(actor.jump(&class("firefox")), Some("0x2".to_string()));
+        assert_eq!(actor.jump(&class("kitty")), Some("0x1".to_string()));
+        assert_eq!(actor.jump(&class("kitty")), Some("0x3".to_string()));
+        assert_eq!(actor.jump(&class("foot")), None);
+    }
+
+    #[test]
+    fn jump_keeps_the_cursor_on_the_only_focused_match() {
+        let mut actor = history_actor(&[("0x1", "firefox"), ("0x2", "kitty")]);
+
+        assert_eq!(actor.jump(&class("kitty")), Some("0x2".to_string()));
+        assert_eq!(
+            actor
+                .event_history
+                .cursor_event()
+                .map(|window_event| window_event.address.as_str()),
+            Some("0x2")
+        );
+    }
+
+    #[test]
+    fn last_picks_the_most_recent_other_window_wherever_the_cursor_is() {
+        let mut actor = history_actor(&[
+            ("0x1", "kitty"),
+            ("0x2", "kitty"),
+            ("0x3", "kitty"),
+            ("0x2", "kitty"),
+        ]);
+
+        assert_eq!(actor.last(), Some("0x3".to_string()));
+        actor.event_history.goto(0);
+        assert_eq!(actor.last(), Some("0x2".to_string()));
+        assert_eq!(history_actor(&[("0x1", "kitty")]).last(), None);
+    }
+
+    #[test]
+    fn frecent_counts_active_windows_only() {
+        let mut actor = history_actor(&[
+            ("0x1", "kitty"),
+            ("0x2", "kitty"),
+            ("0x2", "kitty"),
+            ("0x3", "kitty"),
+            ("0x1", "kitty"),
+            ("0x1", "kitty"),
+        ]);
+        let now = NaiveDateTime::default();
+        let frecent = |actor: &mut FocusHistoryActor, rank| {
+            actor.frecent(NonZeroUsize::new(rank).expect("non-zero rank"), now)
+        };
+
+        actor.event_history.deactivate(&"0x2".to_string());
+
+        assert_eq!(frecent(&mut actor, 1), Some("0x1".to_string()));
+        assert_eq!(frecent(&mut actor, 2), Some("0x3".to_string()));
+        assert_eq!(frecent(&mut actor, 3), None);
+    }
+
+    #[test]
+    fn jump_falls_back_to_cached_windows_missing_from_the_history() {
+        let mut actor = history_actor(&[("0x1", "kitty")]);
+        for (address, class) in [("0x1", "kitty"), ("0x3", "firefox"), ("0x2", "firefox")] {
+            actor.window_cache.window_opened(
+                address.to_string(),
+                "1".to_string(),
+                class.to_string(),
+                String::new(),
+            );
+        }
+
+        assert_eq!(actor.jump(&class("firefox")), Some("0x2".to_string()));
+        assert_eq!(actor.jump(&class("chromium")), None);
+        assert_eq!(
+            actor
+                .event_history
+                .cursor_event()
+                .map(|window_event| window_event.address.as_str()),
+            Some("0x1")
+        );
+    }
+
+    #[tokio::test]
+    async fn reconcile_moves_marks_off_windows_the_reseed_dropped() {
+        let mut actor = history_actor(&[("0x1", "kitty"), ("0x2", "firefox")]);
+        let open = |window_cache: &mut WindowCache, address: &str, class: &str| {
+            window_cache.window_opened(
+                address.to_string(),
+                "1".to_string(),
+                class.to_string(),
+                String::new(),
+            );
+        };
+        open(&mut actor.window_cache, "0x1", "kitty");
+        open(&mut actor.window_cache, "0x2", "firefox");
+        for (mark, address) in [('a', "0x1"), ('b', "0x2")] {
+            let properties = actor
+                .window_cache
+                .window_properties(address)
+                .expect("cached window")
+                .clone();
+            actor
+                .marks
+                .set(mark, address.to_string(), &properties)
+                .await;
+        }
+
+        // The close of 0x1 was missed, and another kitty window opened meanwhile
+        let mut reseeded = WindowCache::default();
+        open(&mut reseeded, "0x2", "firefox");
+        open(&mut reseeded, "0x3", "kitty");
+        actor.window_cache = reseeded;
+        actor.reconcile_with_window_cache();
+
+        assert_eq!(actor.marks.address('a'), Some("0x3"));
+        assert_eq!(actor.marks.address('b'), Some("0x2"));
+    }
+
+    #[tokio::test]
+    async fn records_focus_changes_in_arrival_order() {
+        let focus_history = spawn_focus_history(&[]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "2");
+        open_and_focus(&focus_history, "0x3", "1");
+
+        assert_eq!(
+            listed_addresses(&focus_history).await,
+            vec!["0x1", "0x2", "0x3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn commands_observe_every_earlier_event() {
+        let focus_history = spawn_focus_history(&[]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+        open_and_focus(&focus_history, "0x3", "1");
+        focus_history.send(FocusMessage::WindowClosed {
+            address: "0x2".to_string(),
+        });
+
+        let previous = focus_history
+            .navigate(SocketInstruction::Prev {
+                count: NonZeroUsize::MIN,
+                class: None,
+                scope: None,
+                include_inactive: false,
+                action: FocusAction::default(),
+            })
+            .await
+            .expect("focus history is running");
+
+        assert_eq!(previous.as_deref(), Some("0x1"));
+    }
+
+    #[tokio::test]
+    async fn ignores_focus_on_untracked_monitors() {
+        let focus_history = spawn_focus_history(&["DP-1"]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "2");
+
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x1"]);
+    }
+
+    #[tokio::test]
+    async fn workspace_moves_deactivate_and_reactivate_windows() {
+        let focus_history = spawn_focus_history(&["DP-1"]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+
+        let move_workspace = |monitor: &str| FocusMessage::WorkspaceMoved {
+            workspace: "1".to_string(),
+            monitor: monitor.to_string(),
+        };
+        let statuses = || async {
+            focus_history
+                .list()
+                .await
+                .expect("focus history is running")
+                .into_iter()
+                .map(|window| (window.monitor, window.status))
+                .collect::<Vec<_>>()
+        };
+
+        focus_history.send(move_workspace("HDMI-A-1"));
+        assert_eq!(
+            statuses().await,
+            vec![(Some("HDMI-A-1".to_string()), EntryStatus::Inactive); 2]
+        );
+
+        focus_history.send(move_workspace("DP-1"));
+        assert_eq!(
+            statuses().await,
+            vec![(Some("DP-1".to_string()), EntryStatus::Active); 2]
+        );
+    }
+
+    #[tokio::test]
+    async fn reconfigure_trims_history_and_reevaluates_monitors() {
+        let focus_history = spawn_focus_history(&["DP-1"]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+        open_and_focus(&focus_history, "0x3", "2");
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x1", "0x2"]);
+
+        // Tracking every monitor needs no monitor query, so the new scope applies right away
+        focus_history
+            .reconfigure(
+                HistorySize::try_from(1).expect("size must be non-zero"),
+                SortedDistinctVec::new(Vec::new()),
+                WindowFilters::default(),
+            )
+            .await
+            .expect("focus history is running");
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x2"]);
+
+        open_and_focus(&focus_history, "0x3", "2");
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x3"]);
+    }
+
+    #[tokio::test]
+    async fn window_rules_skip_excluded_windows_and_follow_title_changes() {
+        let window_filters = WindowFilters {
+            include: Vec::new(),
+            exclude: vec![toml::from_str("title = \"re:Picture-in-Picture\"").expect("valid rule")],
+        };
+        let focus_history = spawn_filtered_focus_history(&[], window_filters);
+        let change_title = |title: &str| FocusMessage::WindowTitleChanged {
+            address: "0x2".to_string(),
+            title: title.to_string(),
+        };
+        let statuses = || async {
+            focus_history
+                .list()
+                .await
+                .expect("focus history is running")
+                .into_iter()
+                .map(|window| (window.address, window.status))
+                .collect::<Vec<_>>()
+        };
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+        open_and_focus(&focus_history, "0x3", "1");
+        focus_history.send(change_title("Picture-in-Picture"));
+        focus_history.send(FocusMessage::ActiveWindowChanged {
+            address: "0x2".to_string(),
+            time: NaiveDateTime::default(),
+        });
+        assert_eq!(
+            statuses().await,
+            vec![
+                ("0x1".to_string(), EntryStatus::Active),
+                ("0x2".to_string(), EntryStatus::Inactive),
+                ("0x3".to_string(), EntryStatus::Active),
+            ]
+        );
+
+        focus_history.send(change_title("YouTube"));
+        assert_eq!(
+            statuses().await,
+            vec![
+                ("0x1".to_string(), EntryStatus::Active),
+                ("0x2".to_string(), EntryStatus::Active),
+                ("0x3".to_string(), EntryStatus::Active),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn entries_record_and_follow_window_properties() {
+        let focus_history = spawn_focus_history(&[]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        focus_history.send(FocusMessage::WindowTitleChanged {
+            address: "0x1".to_string(),
+            title: "vim".to_string(),
+        });
+        focus_history.send(FocusMessage::FullscreenChanged { fullscreen: true });
+        focus_history.send(FocusMessage::WindowMoved {
+            address: "0x1".to_string(),
+            workspace: "2".to_string(),
+            workspace_id: 2,
+            time: NaiveDateTime::default(),
+        });
+
+        let listed = focus_history
+            .list()
+            .await
+            .expect("focus history is running");
+        let properties = &listed.first().expect("window is listed").properties;
+        assert_eq!(properties.class, "kitty");
+        assert_eq!(properties.initial_title, "window 0x1");
+        assert_eq!(properties.title, "vim");
+        assert_eq!(
+            (properties.workspace.as_str(), properties.workspace_id),
+            ("2", 2)
+        );
+        assert!(properties.fullscreen);
+    }
+
+    #[tokio::test]
+    async fn class_traversal_skips_other_classes() {
+        let focus_history = spawn_focus_history(&[]);
+
+        open_and_focus_class(&focus_history, "0x1", "1", "firefox");
+        open_and_focus_class(&focus_history, "0x2", "1", "kitty");
+        open_and_focus_class(&focus_history, "0x3", "1", "chromium");
+        open_and_focus_class(&focus_history, "0x4", "1", "kitty");
+        let prev = |class: &str| SocketInstruction::Prev {
+            count: NonZeroUsize::MIN,
+            class: Some(class.parse().expect("valid class pattern")),
+            scope: None,
+            include_inactive: false,
+            action: FocusAction::default(),
+        };
+
+        let previous_browser = focus_history
+            .navigate(prev("re:^(firefox|chromium)$"))
+            .await
+            .expect("focus history is running");
+        assert_eq!(previous_browser.as_deref(), Some("0x3"));
+
+        let previous_terminal = focus_history
+            .navigate(prev("kitty"))
+            .await
+            .expect("focus history is running");
+        assert_eq!(previous_terminal.as_deref(), Some("0x2"));
+
+        let no_match = focus_history
+            .navigate(prev("foot"))
+            .await
+            .expect("focus history is running");
+        assert_eq!(no_match, None);
+    }
+
+    #[tokio::test]
+    async fn scoped_traversal_follows_windows_to_their_current_location() {
+        let focus_history = spawn_focus_history(&[]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "2");
+        open_and_focus(&focus_history, "0x3", "1");
+        open_and_focus(&focus_history, "0x4", "1");
+        focus_history.send(FocusMessage::WindowMoved {
+            address: "0x3".to_string(),
+            workspace: "2".to_string(),
+            workspace_id: 2,
+            time: NaiveDateTime::default(),
+        });
+
+        let on_workspace = focus_history
+            .navigate(SocketInstruction::Prev {
+                count: NonZeroUsize::MIN,
+                class: None,
+                scope: Some(ScopeLocation::Workspaces(vec!["1".to_string()])),
+                include_inactive: false,
+                action: FocusAction::default(),
+            })
+            .await
+            .expect("focus history is running");
+        assert_eq!(on_workspace.as_deref(), Some("0x1"));
+
+        let on_monitor = focus_history
+            .navigate(SocketInstruction::Next {
+                count: NonZeroUsize::new(2).expect("non-zero"),
+                class: None,
+                scope: Some(ScopeLocation::Monitor("HDMI-A-1".to_string())),
+                include_inactive: false,
+                action: FocusAction::default(),
+            })
+            .await
+            .expect("focus history is running");
+        assert_eq!(on_monitor.as_deref(), Some("0x3"));
+    }
+
+    #[tokio::test]
+    async fn moves_made_by_focus_actions_are_not_recorded() {
+        let focus_history = spawn_focus_history(&["DP-1"]);
+        let move_to_tracked_workspace = |address: &str| FocusMessage::WindowMoved {
+            address: address.to_string(),
+            workspace: "1".to_string(),
+            workspace_id: 1,
+            time: NaiveDateTime::default(),
+        };
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "2");
+        open_and_focus(&focus_history, "0x3", "2");
+        focus_history.send(FocusMessage::ActionMove {
+            address: "0x2".to_string(),
+        });
+        focus_history.send(move_to_tracked_workspace("0x2"));
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x1"]);
+
+        focus_history.send(move_to_tracked_workspace("0x3"));
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x1", "0x3"]);
+    }
+
+    #[tokio::test]
+    async fn moves_after_a_failed_focus_action_are_recorded() {
+        let focus_history = spawn_focus_history(&["DP-1"]);
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "2");
+        focus_history.send(FocusMessage::ActionMove {
+            address: "0x2".to_string(),
+        });
+        focus_history.send(FocusMessage::ActionMoveFailed {
+            address: "0x2".to_string(),
+        });
+        focus_history.send(FocusMessage::WindowMoved {
+            address: "0x2".to_string(),
+            workspace: "1".to_string(),
+            workspace_id: 1,
+            time: NaiveDateTime::default(),
+        });
+
+        assert_eq!(listed_addresses(&focus_history).await, vec!["0x1", "0x2"]);
+    }
+
+    #[tokio::test]
+    async fn jumping_to_a_mark_pushes_its_window_until_it_closes() {
+        let focus_history = spawn_focus_history(&[]);
+        let jump = || SocketInstruction::JumpToMark {
+            mark: 'a',
+            action: FocusAction::default(),
+        };
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "2");
+        open_and_focus(&focus_history, "0x3", "1");
+        focus_history.set_mark('a', "0x1".to_string());
+        focus_history
+            .navigate(SocketInstruction::Prev {
+                count: NonZeroUsize::MIN,
+                class: None,
+                scope: None,
+                include_inactive: false,
+                action: FocusAction::default(),
+            })
+            .await
+            .expect("focus history is running");
+
+        let jumped = focus_history
+            .navigate(jump())
+            .await
+            .expect("focus history is running");
+        assert_eq!(jumped.as_deref(), Some("0x1"));
+        assert_eq!(
+            listed_addresses(&focus_history).await,
+            vec!["0x1", "0x2", "0x3"]
+        );
+
+        focus_history.send(FocusMessage::ActiveWindowChanged {
+            address: "0x1".to_string(),
+            time: NaiveDateTime::default(),
+        });
+        assert_eq!(
+            listed_addresses(&focus_history).await,
+            vec!["0x1", "0x2", "0x1"]
+        );
+
+        focus_history.send(FocusMessage::WindowClosed {
+            address: "0x1".to_string(),
+        });
+        let jumped = focus_history
+            .navigate(jump())
+            .await
+            .expect("focus history is running");
+        assert_eq!(jumped, None);
+        let marks = focus_history
+            .list_marks()
+            .await
+            .expect("focus history is running");
+        assert_eq!(marks.len(), 1);
+        assert_eq!(marks[0].address, None);
+
+        focus_history.clear_mark('a');
+        assert!(
+            focus_history
+                .list_marks()
+                .await
+                .expect("focus history is running")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn jump_cycles_matching_windows_by_recency() {
+        let focus_history = spawn_focus_history(&[]);
+        let jump = |class: &str| SocketInstruction::Jump {
+            class: class.parse().expect("valid class pattern"),
+            exec: None,
+            action: FocusAction::default(),
+        };
+
+        open_and_focus_class(&focus_history, "0x1", "1", "kitty");
+        open_and_focus_class(&focus_history, "0x2", "1", "firefox");
+        open_and_focus_class(&focus_history, "0x3", "1", "kitty");
+        open_and_focus_class(&focus_history, "0x4", "1", "firefox");
+        open_and_focus(&focus_history, "0x1", "1");
+
+        let mut jumped = Vec::new();
+        for _ in 0..3 {
+            jumped.push(
+                focus_history
+                    .navigate(jump("firefox"))
+                    .await
+                    .expect("focus history is running"),
+            );
+        }
+        assert_eq!(
+            jumped,
+            vec![
+                Some("0x4".to_string()),
+                Some("0x2".to_string()),
+                Some("0x4".to_string())
+            ]
+        );
+
+        let no_match = focus_history
+            .navigate(jump("foot"))
+            .await
+            .expect("focus history is running");
+        assert_eq!(no_match, None);
+    }
+
+    #[tokio::test]
+    async fn last_toggles_between_the_two_most_recent_windows() {
+        let focus_history = spawn_focus_history(&[]);
+        let last = || SocketInstruction::Last {
+            action: FocusAction::default(),
+        };
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+        open_and_focus(&focus_history, "0x3", "1");
+
+        let mut toggled = Vec::new();
+        for _ in 0..3 {
+            toggled.push(
+                focus_history
+                    .navigate(last())
+                    .await
+                    .expect("focus history is running"),
+            );
+        }
+        assert_eq!(
+            toggled,
+            vec![
+                Some("0x2".to_string()),
+                Some("0x3".to_string()),
+                Some("0x2".to_string())
+            ]
+        );
+
+        // Away from the newest entries, the most recent window is still the one picked
+        focus_history
+            .navigate(SocketInstruction::Goto {
+                index: 0,
+                action: FocusAction::default(),
+            })
+            .await
+            .expect("focus history is running");
+        let from_oldest = focus_history
+            .navigate(last())
+            .await
+            .expect("focus history is running");
+        assert_eq!(from_oldest, Some("0x3".to_string()));
+    }
+
+    #[tokio::test]
+    async fn inactive_traversal_reaches_windows_on_untracked_monitors() {
+        let focus_history = spawn_focus_history(&["DP-1"]);
+        let prev = |include_inactive| SocketInstruction::Prev {
+            count: NonZeroUsize::MIN,
+            class: None,
+            scope: None,
+            include_inactive,
+            action: FocusAction::default(),
+        };
+        let move_window = |workspace: &str, workspace_id| FocusMessage::WindowMoved {
+            address: "0x2".to_string(),
+            workspace: workspace.to_string(),
+            workspace_id,
+            time: NaiveDateTime::default(),
+        };
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+        open_and_focus(&focus_history, "0x3", "1");
+        focus_history.send(move_window("2", 2));
+
+        let skipped = focus_history
+            .navigate(prev(false))
+            .await
+            .expect("focus history is running");
+        assert_eq!(skipped.as_deref(), Some("0x1"));
+        focus_history
+            .navigate(SocketInstruction::Goto {
+                index: 2,
+                action: FocusAction::default(),
+            })
+            .await
+            .expect("focus history is running");
+
+        let reached = focus_history
+            .navigate(prev(true))
+            .await
+            .expect("focus history is running");
+        assert_eq!(reached.as_deref(), Some("0x2"));
+
+        // The window is brought to the tracked monitor and focused without a new entry
+        focus_history.send(FocusMessage::ActionMove {
+            address: "0x2".to_string(),
+        });
+        focus_history.send(move_window("1", 1));
+        focus_history.send(FocusMessage::ActiveWindowChanged {
+            address: "0x2".to_string(),
+            time: NaiveDateTime::default(),
+        });
+        let statuses: Vec<_> = focus_history
+            .list()
+            .await
+            .expect("focus history is running")
+            .into_iter()
+            .map(|window| (window.address, window.status, window.current))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("0x1".to_string(), EntryStatus::Active, false),
+                ("0x2".to_string(), EntryStatus::Active, true),
+                ("0x3".to_string(), EntryStatus::Active, false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn frecent_ranks_windows_by_focus_count_then_recency() {
+        let focus_history = spawn_focus_history(&[]);
+        let frecent = |rank| SocketInstruction::Frecent {
+            rank: NonZeroUsize::new(rank).expect("non-zero rank"),
+            action: FocusAction::default(),
+        };
+
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x2", "1");
+        open_and_focus(&focus_history, "0x1", "1");
+        open_and_focus(&focus_history, "0x3", "1");
+
+        let ranked: Vec<_> = focus_history
+            .list_by_frecency()
+            .await
+            .expect("focus history is running")
+            .into_iter()
+            .map(|window| (window.index, window.address, window.frecency))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (2, "0x1".to_string(), Some(0.5)),
+                (3, "0x3".to_string(), Some(0.25)),
+                (1, "0x2".to_string(), Some(0.25)),
+            ]
+        );
+
+        let mut focused = Vec::new();
+        for rank in [1, 3, 4] {
+            focused.push(
+                focus_history
+                    .navigate(frecent(rank))
+                    .await
+                    .expect("focus history is running"),
+            );
+        }
+        assert_eq!(
+            focused,
+            vec![Some("0x1".to_string()), Some("0x2".to_string()), None]
+        );
+    }
+}
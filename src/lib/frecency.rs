@@ -0,0 +1,117 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::{event_history::HistoryEntry, types::WindowEvent};
+
+/// A window with the latest of its history entries and its frecency score
+#[derive(Debug)]
+pub(crate) struct RankedWindow<'a> {
+    pub entry: HistoryEntry<'a, WindowEvent>,
+    pub score: f64,
+}
+
+/// Weight of a focus `age` ago, as zoxide weighs directory visits
+fn recency_weight(age: TimeDelta) -> f64 {
+    if age < TimeDelta::hours(1) {
+        4.0
+    } else if age < TimeDelta::days(1) {
+        2.0
+    } else if age < TimeDelta::weeks(1) {
+        0.5
+    } else {
+        0.25
+    }
+}
+
+/// Ranks every window in `entries`, given oldest first, by its number of entries weighted by how
+/// long before `now` it was last focused. Highest scores come first, ties go to the most recent
+/// window.
+pub(crate) fn rank<'a>(
+    entries: impl IntoIterator<Item = HistoryEntry<'a, WindowEvent>>,
+    now: NaiveDateTime,
+) -> Vec<RankedWindow<'a>> {
+    let mut windows: HashMap<&str, (u32, HistoryEntry<'a, WindowEvent>)> = HashMap::new();
+    for entry in entries {
+        match windows.entry(entry.event.address.as_str()) {
+            Entry::Occupied(mut window) => {
+                let (count, latest) = window.get_mut();
+                *count += 1;
+                *latest = entry;
+            }
+            Entry::Vacant(window) => {
+                window.insert((1, entry));
+            }
+        }
+    }
+
+    let mut ranked: Vec<RankedWindow> = windows
+        .into_values()
+        .map(|(count, entry)| RankedWindow {
+            score: f64::from(count) * recency_weight(now - entry.event.time),
+            entry,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.entry.index.cmp(&a.entry.index))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDateTime, TimeDelta};
+
+    use super::rank;
+    use crate::{
+        event_history::{EntryStatus, HistoryEntry},
+        types::WindowEvent,
+        window_cache::WindowProperties,
+    };
+
+    fn window_event(address: &str, time: NaiveDateTime) -> WindowEvent {
+        WindowEvent {
+            address: address.to_string(),
+            monitor: None,
+            time,
+            properties: WindowProperties::default(),
+        }
+    }
+
+    #[test]
+    fn recent_focus_outweighs_frequent_focus_until_it_ages() {
+        let now = NaiveDateTime::default() + TimeDelta::weeks(2);
+        let events = [
+            window_event("0x1", now - TimeDelta::days(3)),
+            window_event("0x1", now - TimeDelta::days(3)),
+            window_event("0x1", now - TimeDelta::days(2)),
+            window_event("0x2", now - TimeDelta::hours(5)),
+            window_event("0x3", now - TimeDelta::minutes(5)),
+        ];
+        let entries = || {
+            events
+                .iter()
+                .enumerate()
+                .map(|(index, event)| HistoryEntry {
+                    index,
+                    status: EntryStatus::Active,
+                    current: false,
+                    event,
+                })
+        };
+        let ranking = |now| {
+            rank(entries(), now)
+                .into_iter()
+                .map(|ranked| (ranked.entry.event.address.as_str(), ranked.score))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ranking(now), vec![("0x3", 4.0), ("0x2", 2.0), ("0x1", 1.5)]);
+        assert_eq!(
+            ranking(now + TimeDelta::days(1)),
+            vec![("0x1", 1.5), ("0x3", 0.5), ("0x2", 0.5)]
+        );
+    }
+}
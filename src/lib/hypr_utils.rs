@@ -1,11 +1,23 @@
 use chrono::Local;
 use hyprland::{
-    data::{Client, Clients, Monitors},
-    shared::{Address, HyprData, HyprDataActiveOptional},
+    data::{Client, Monitors},
+    shared::{HyprData, HyprDataActiveOptional},
 };
-use log::error;
+use log::{debug, error};
 
-use crate::types::{SortedDistinctVec, WindowEvent};
+use crate::{monitor_selector::TrackedMonitors, types::WindowEvent, window_cache::WindowCache};
+
+#[must_use]
+pub fn window_on_requested_monitor(
+    window_event: &WindowEvent,
+    tracked_monitors: &TrackedMonitors,
+) -> bool {
+    tracked_monitors.tracks_all()
+        || window_event
+            .monitor
+            .as_ref()
+            .is_some_and(|event_monitor| tracked_monitors.contains(event_monitor))
+}
 
 pub async fn current_focused_window_event() -> Option<WindowEvent> {
     let time = Local::now().naive_local();
@@ -32,33 +44,23 @@ pub async fn current_focused_window_event() -> Option<WindowEvent> {
         }),
         address: active_client.address.to_string(),
         time,
+        properties: active_client.into(),
     })
 }
 
-async fn get_window_monitor(address: &Address) -> Option<String> {
-    let (clients, monitors) = match tokio::try_join!(Clients::get_async(), Monitors::get_async()) {
-        Ok((clients, monitors)) => (clients, monitors),
-        Err(e) => {
-            error!("Failed to query hyprland monitors and clients: {e}");
-            return None;
-        }
-    };
+async fn get_window_monitor(window_cache: &mut WindowCache, address: &str) -> Option<String> {
+    if let Some(monitor) = window_cache.window_monitor(address) {
+        return Some(monitor.to_string());
+    }
 
-    let monitor_at_address = clients.iter().find_map(|c| {
-        if c.address == *address {
-            c.monitor
-        } else {
-            None
-        }
-    })?;
+    // The events placing this window or its workspace may have been missed
+    debug!("Window cache miss for {address}; reseeding");
+    if let Err(e) = window_cache.reseed().await {
+        error!("Failed to query hyprland workspaces and clients: {e}");
+        return None;
+    }
 
-    monitors.into_iter().find_map(|m| {
-        if m.id == monitor_at_address {
-            Some(m.name)
-        } else {
-            None
-        }
-    })
+    window_cache.window_monitor(address).map(str::to_string)
 }
 
 pub enum WindowMonitorRequest {
@@ -68,17 +70,18 @@ pub enum WindowMonitorRequest {
 }
 
 pub async fn get_window_monitor_request(
-    address: &Address,
-    requested_monitors: &'static SortedDistinctVec<String>,
+    window_cache: &mut WindowCache,
+    address: &str,
+    tracked_monitors: &TrackedMonitors,
 ) -> WindowMonitorRequest {
-    match get_window_monitor(address).await {
+    match get_window_monitor(window_cache, address).await {
         Some(monitor) => {
-            if requested_monitors.get().is_empty() {
+            if tracked_monitors.tracks_all() {
                 return WindowMonitorRequest::AllRequested {
                     window_monitor: monitor,
                 };
             }
-            if requested_monitors.get().contains(&monitor) {
+            if tracked_monitors.contains(&monitor) {
                 WindowMonitorRequest::Matching {
                     window_monitor: monitor,
                 }
@@ -1,6 +1,20 @@
 pub mod cli;
+pub mod config;
 pub mod daemon;
+#[cfg(feature = "dbus")]
+pub mod dbus;
 pub mod event_history;
+pub mod focus_action;
+pub mod focus_history;
+pub mod frecency;
 pub mod hypr_utils;
+pub mod marks;
+pub mod monitor_selector;
+pub mod reload;
+pub mod scope;
+pub mod shutdown;
 pub mod socket;
+pub mod systemd;
 pub mod types;
+pub mod window_cache;
+pub mod window_filter;
@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::window_cache::{WindowCache, WindowProperties};
+
+const STATE_DIR: &str = "hyprhist";
+
+/// `$XDG_STATE_HOME/hyprhist/marks.json`, or `marks.<tracker>.json` for a tracker's daemon,
+/// falling back to `~/.local/state` when `XDG_STATE_HOME` isn't set
+#[must_use]
+pub fn default_marks_path(tracker: Option<&str>) -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .filter(|state_home| !state_home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+
+    let file_name = match tracker {
+        Some(tracker) => format!("marks.{tracker}.json"),
+        None => "marks.json".to_string(),
+    };
+
+    Some(state_home.join(STATE_DIR).join(file_name))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Mark {
+    /// `None` while no open window carries the mark, e.g. after its window closed
+    address: Option<String>,
+    class: String,
+    title: String,
+}
+
+/// How a mark is written to disk; windows are found again by class and title
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredMark {
+    mark: char,
+    class: String,
+    title: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct ListedMark {
+    pub mark: char,
+    pub address: Option<String>,
+    pub class: String,
+    pub title: String,
+}
+
+/// Windows tagged with a single character, which stay with the window until it closes.
+/// A mark outlives its window and is reattached to the next window of the same class,
+/// preferring one with the same title, so with a path it also survives a restart.
+/// Only setting the mark again or clearing it drops it.
+#[derive(Debug, Default)]
+pub struct Marks {
+    marks: BTreeMap<char, Mark>,
+    path: Option<PathBuf>,
+}
+
+fn read_marks(path: &Path) -> anyhow::Result<Vec<StoredMark>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    serde_json::from_str(&contents).with_context(|| format!("Invalid marks in {}", path.display()))
+}
+
+async fn write_marks(path: &Path, stored: &[StoredMark]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, serde_json::to_vec(stored)?).await
+}
+
+impl Marks {
+    /// Marks stored at `path`, or none when marks aren't persisted
+    #[must_use]
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let stored = match path.as_deref().map(read_marks).transpose() {
+            Ok(stored) => stored.unwrap_or_default(),
+            Err(err) => {
+                warn!("Starting without stored marks: {err:#}");
+                Vec::new()
+            }
+        };
+
+        Self {
+            marks: stored
+                .into_iter()
+                .map(|stored| {
+                    (
+                        stored.mark,
+                        Mark {
+                            address: None,
+                            class: stored.class,
+                            title: stored.title,
+                        },
+                    )
+                })
+                .collect(),
+            path,
+        }
+    }
+
+    async fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let stored: Vec<StoredMark> = self
+            .marks
+            .iter()
+            .map(|(mark, window)| StoredMark {
+                mark: *mark,
+                class: window.class.clone(),
+                title: window.title.clone(),
+            })
+            .collect();
+
+        if let Err(err) = write_marks(path, &stored).await {
+            warn!("Failed to store marks in {}: {err}", path.display());
+        }
+    }
+
+    fn is_marked(&self, address: &str) -> bool {
+        self.marks
+            .values()
+            .any(|window| window.address.as_deref() == Some(address))
+    }
+
+    /// Attaches unresolved marks to the cached windows that match them best
+    pub fn resolve(&mut self, window_cache: &WindowCache) {
+        let unresolved: Vec<char> = self
+            .marks
+            .iter()
+            .filter(|(_, window)| window.address.is_none())
+            .map(|(mark, _)| *mark)
+            .collect();
+        if unresolved.is_empty() {
+            return;
+        }
+
+        let mut windows: Vec<(&str, &WindowProperties)> = window_cache.windows().collect();
+        windows.sort_unstable_by_key(|(address, _)| *address);
+
+        for mark in unresolved {
+            let window = &self.marks[&mark];
+            let candidates = || {
+                windows.iter().filter(|(address, properties)| {
+                    properties.class == window.class && !self.is_marked(address)
+                })
+            };
+            let Some((address, _)) = candidates()
+                .find(|(_, properties)| properties.title == window.title)
+                .or_else(|| candidates().next())
+            else {
+                continue;
+            };
+
+            debug!("Reattached mark '{mark}' to {address}");
+            let address = (*address).to_string();
+            if let Some(window) = self.marks.get_mut(&mark) {
+                window.address = Some(address);
+            }
+        }
+    }
+
+    pub async fn set(&mut self, mark: char, address: String, properties: &WindowProperties) {
+        self.marks.insert(
+            mark,
+            Mark {
+                address: Some(address),
+                class: properties.class.clone(),
+                title: properties.title.clone(),
+            },
+        );
+        self.save().await;
+    }
+
+    #[must_use]
+    pub fn address(&self, mark: char) -> Option<&str> {
+        self.marks.get(&mark)?.address.as_deref()
+    }
+
+    /// Removes `mark`, returning whether it was set
+    pub async fn clear(&mut self, mark: char) -> bool {
+        let cleared = self.marks.remove(&mark).is_some();
+        if cleared {
+            self.save().await;
+        }
+        cleared
+    }
+
+    /// Unbinds marks from windows that `window_cache` no longer knows, e.g. after a reseed
+    pub fn unbind_missing(&mut self, window_cache: &WindowCache) {
+        for window in self.marks.values_mut() {
+            if window
+                .address
+                .as_deref()
+                .is_some_and(|address| !window_cache.contains_window(address))
+            {
+                window.address = None;
+            }
+        }
+    }
+
+    /// Unbinds the marks of a closed window, keeping them for [`Marks::resolve`] to reattach
+    pub fn window_closed(&mut self, address: &str) {
+        for window in self.marks.values_mut() {
+            if window.address.as_deref() == Some(address) {
+                window.address = None;
+            }
+        }
+    }
+
+    /// Every mark with the current class and title of its window, when it's cached
+    pub(crate) fn list(&self, window_cache: &WindowCache) -> Vec<ListedMark> {
+        self.marks
+            .iter()
+            .map(|(mark, window)| {
+                let properties = window
+                    .address
+                    .as_deref()
+                    .and_then(|address| window_cache.window_properties(address));
+
+                ListedMark {
+                    mark: *mark,
+                    address: window.address.clone(),
+                    class: properties
+                        .map_or(&window.class, |properties| &properties.class)
+                        .clone(),
+                    title: properties
+                        .map_or(&window.title, |properties| &properties.title)
+                        .clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Marks;
+    use crate::window_cache::WindowCache;
+
+    fn open_window(window_cache: &mut WindowCache, address: &str, class: &str, title: &str) {
+        window_cache.window_opened(
+            address.to_string(),
+            "1".to_string(),
+            class.to_string(),
+            title.to_string(),
+        );
+    }
+
+    #[tokio::test]
+    async fn closing_a_window_unbinds_its_marks_until_a_match_opens() {
+        let mut window_cache = WindowCache::default();
+        open_window(&mut window_cache, "0x1", "kitty", "zsh");
+        let properties = window_cache
+            .window_properties("0x1")
+            .expect("cached window")
+            .clone();
+        let mut marks = Marks::default();
+
+        marks.set('a', "0x1".to_string(), &properties).await;
+        marks.set('b', "0x1".to_string(), &properties).await;
+        assert_eq!(marks.address('a'), Some("0x1"));
+
+        window_cache.window_closed("0x1");
+        marks.window_closed("0x1");
+        assert_eq!(marks.address('a'), None);
+        let listed = marks.list(&window_cache);
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().all(|mark| mark.address.is_none()));
+
+        open_window(&mut window_cache, "0x2", "kitty", "zsh");
+        marks.resolve(&window_cache);
+        assert_eq!(marks.address('a'), Some("0x2"));
+        assert!(marks.clear('a').await);
+        assert!(!marks.clear('a').await);
+        assert_eq!(marks.list(&window_cache).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stored_marks_reattach_by_class_then_title() {
+        let dir = std::env::temp_dir().join(format!("hyprhist-marks-{}", std::process::id()));
+        let path = dir.join("marks.json");
+        let mut window_cache = WindowCache::default();
+        open_window(&mut window_cache, "0x1", "kitty", "htop");
+        open_window(&mut window_cache, "0x2", "kitty", "vim");
+        open_window(&mut window_cache, "0x3", "firefox", "News");
+
+        let mut marks = Marks::load(Some(path.clone()));
+        for (mark, address) in [('e', "0x2"), ('w', "0x3")] {
+            let properties = window_cache
+                .window_properties(address)
+                .expect("cached window")
+                .clone();
+            marks.set(mark, address.to_string(), &properties).await;
+        }
+
+        // After a restart every window has a new address
+        let mut window_cache = WindowCache::default();
+        open_window(&mut window_cache, "0x11", "kitty", "htop");
+        open_window(&mut window_cache, "0x12", "kitty", "vim");
+        open_window(&mut window_cache, "0x13", "firefox", "Mail");
+        let mut marks = Marks::load(Some(path));
+        assert_eq!(marks.address('e'), None);
+
+        marks.resolve(&window_cache);
+        assert_eq!(marks.address('e'), Some("0x12"));
+        assert_eq!(marks.address('w'), Some("0x13"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
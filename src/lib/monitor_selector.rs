@@ -0,0 +1,264 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use hyprland::{data::Monitors, shared::HyprData};
+use log::info;
+use regex::Regex;
+
+use crate::types::SortedDistinctVec;
+
+const DESCRIPTION_PREFIX: &str = "desc:";
+pub(crate) const REGEX_PREFIX: &str = "re:";
+const NEGATION_PREFIX: char = '!';
+
+#[derive(Clone, Debug)]
+enum MonitorPattern {
+    Name(String),
+    Description(Regex),
+    Regex(Regex),
+}
+
+/// A `--monitor` value: a connector name such as `DP-1`, a `desc:` glob over the monitor
+/// description, or a `re:` regex over the connector name, optionally negated with a leading `!`
+#[derive(Clone, Debug)]
+pub struct MonitorSelector {
+    source: String,
+    negated: bool,
+    pattern: MonitorPattern,
+}
+
+fn glob_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    for glob_char in glob.chars() {
+        match glob_char {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            _ => pattern.push_str(&regex::escape(glob_char.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+impl FromStr for MonitorSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Selectors end up in the socket path, separated by `::`, which a `:` next to a
+        // separator would make ambiguous
+        if s.contains('/') || s.contains("::") {
+            return Err(format!(
+                "monitor selector '{s}' must not contain '/' or '::'"
+            ));
+        }
+        if s.starts_with(':') || s.ends_with(':') {
+            return Err(format!(
+                "monitor selector '{s}' must not start or end with ':'"
+            ));
+        }
+
+        let (negated, selector) = match s.strip_prefix(NEGATION_PREFIX) {
+            Some(selector) => (true, selector),
+            None => (false, s),
+        };
+
+        if selector.is_empty() {
+            return Err("monitor selector must not be empty".to_string());
+        }
+
+        let pattern = if let Some(glob) = selector.strip_prefix(DESCRIPTION_PREFIX) {
+            MonitorPattern::Description(
+                glob_regex(glob).map_err(|err| format!("invalid description glob: {err}"))?,
+            )
+        } else if let Some(regex) = selector.strip_prefix(REGEX_PREFIX) {
+            MonitorPattern::Regex(
+                Regex::new(regex).map_err(|err| format!("invalid monitor regex: {err}"))?,
+            )
+        } else {
+            MonitorPattern::Name(selector.to_string())
+        };
+
+        Ok(Self {
+            source: s.to_string(),
+            negated,
+            pattern,
+        })
+    }
+}
+
+impl Display for MonitorSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+// Selectors are identified by how they were written, which is also how they appear in socket
+// paths
+impl PartialEq for MonitorSelector {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for MonitorSelector {}
+
+impl PartialOrd for MonitorSelector {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MonitorSelector {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.source.cmp(&other.source)
+    }
+}
+
+impl MonitorSelector {
+    fn pattern_matches(&self, name: &str, description: &str) -> bool {
+        match &self.pattern {
+            MonitorPattern::Name(selected_name) => selected_name == name,
+            MonitorPattern::Description(regex) => regex.is_match(description),
+            MonitorPattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Whether the monitor is selected: it has to match any of the non-negated selectors, or there
+/// must be none, and none of the negated ones
+#[must_use]
+pub fn selects_monitor(selectors: &[MonitorSelector], name: &str, description: &str) -> bool {
+    let mut positive_selectors = selectors
+        .iter()
+        .filter(|selector| !selector.negated)
+        .peekable();
+
+    let included = positive_selectors.peek().is_none()
+        || positive_selectors.any(|selector| selector.pattern_matches(name, description));
+
+    included
+        && !selectors
+            .iter()
+            .filter(|selector| selector.negated)
+            .any(|selector| selector.pattern_matches(name, description))
+}
+
+/// Names of the connected monitors selected by a daemon's `--monitor` selectors, resolved again
+/// whenever monitors change
+#[derive(Clone, Debug)]
+pub struct TrackedMonitors {
+    selectors: SortedDistinctVec<MonitorSelector>,
+    names: Vec<String>,
+}
+
+impl TrackedMonitors {
+    #[must_use]
+    pub fn new(selectors: SortedDistinctVec<MonitorSelector>) -> Self {
+        Self {
+            selectors,
+            names: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn tracks_all(&self) -> bool {
+        self.selectors.get().is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, monitor: &str) -> bool {
+        self.tracks_all() || self.names.iter().any(|name| name == monitor)
+    }
+
+    /// Resolves the selectors against `(name, description)` pairs of the connected monitors
+    pub fn resolve<'a>(&mut self, monitors: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        let names: Vec<String> = monitors
+            .into_iter()
+            .filter(|(name, description)| selects_monitor(self.selectors.get(), name, description))
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        if !self.tracks_all() && names != self.names {
+            info!("Tracking monitors {names:?}");
+        }
+        self.names = names;
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn refresh(&mut self) -> hyprland::Result<()> {
+        let monitors = Monitors::get_async().await?;
+        self.resolve(
+            monitors
+                .iter()
+                .map(|monitor| (monitor.name.as_str(), monitor.description.as_str())),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MonitorSelector, TrackedMonitors, selects_monitor};
+    use crate::types::SortedDistinctVec;
+
+    const DELL: (&str, &str) = ("DP-1", "Dell Inc. DELL U2720Q 1234");
+    const LG: (&str, &str) = ("DP-2", "LG Electronics 27GL850 5678");
+    const LAPTOP: (&str, &str) = ("eDP-1", "BOE 0x095F");
+
+    fn selectors(sources: &[&str]) -> Vec<MonitorSelector> {
+        sources
+            .iter()
+            .map(|source| source.parse().expect("valid selector"))
+            .collect()
+    }
+
+    fn selected(sources: &[&str]) -> Vec<&'static str> {
+        let selectors = selectors(sources);
+        [DELL, LG, LAPTOP]
+            .into_iter()
+            .filter(|(name, description)| selects_monitor(&selectors, name, description))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn selects_by_exact_name() {
+        assert_eq!(selected(&["DP-1"]), vec!["DP-1"]);
+        assert!(selected(&["DP"]).is_empty());
+    }
+
+    #[test]
+    fn selects_by_description_glob() {
+        assert_eq!(selected(&["desc:Dell Inc. DELL U2720Q*"]), vec!["DP-1"]);
+        assert_eq!(selected(&["desc:*27GL85?*"]), vec!["DP-2"]);
+        assert!(selected(&["desc:Dell"]).is_empty());
+    }
+
+    #[test]
+    fn selects_by_regex_over_name() {
+        assert_eq!(selected(&["re:^DP-.*"]), vec!["DP-1", "DP-2"]);
+    }
+
+    #[test]
+    fn negated_selectors_exclude_monitors() {
+        assert_eq!(selected(&["!eDP-1"]), vec!["DP-1", "DP-2"]);
+        assert_eq!(selected(&["re:DP", "!desc:LG*"]), vec!["DP-1", "eDP-1"]);
+    }
+
+    #[test]
+    fn rejects_invalid_selectors() {
+        for source in ["", "!", "re:(", "DP/1", "DP::1", ":DP-1", "re:DP:", "desc:"] {
+            assert!(source.parse::<MonitorSelector>().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn tracks_nothing_when_no_monitor_is_selected() {
+        let mut tracked_monitors =
+            TrackedMonitors::new(SortedDistinctVec::new(selectors(&["HDMI-A-1"])));
+
+        tracked_monitors.resolve([DELL, LG]);
+
+        assert!(!tracked_monitors.contains("DP-1"));
+        assert!(!tracked_monitors.contains("DP-2"));
+    }
+}
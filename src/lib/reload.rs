@@ -0,0 +1,175 @@
+use std::path::PathBuf;
+
+use log::{error, info, warn};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::{mpsc, oneshot, watch},
+};
+
+use crate::{
+    cli::DaemonArgs,
+    config::{ActionsConfig, Config, DaemonSettings},
+    focus_history::FocusHistory,
+    monitor_selector::MonitorSelector,
+    systemd,
+    types::SortedDistinctVec,
+};
+
+/// Where daemon settings are rebuilt from on every reload
+#[derive(Debug)]
+pub struct ConfigSource {
+    pub config_path: Option<PathBuf>,
+    pub daemon_args: &'static DaemonArgs,
+}
+
+impl ConfigSource {
+    #[allow(clippy::missing_errors_doc)]
+    pub fn settings(&self) -> anyhow::Result<DaemonSettings> {
+        Config::load_or_default(self.config_path.as_deref())?.daemon_settings(self.daemon_args)
+    }
+}
+
+type ReloadReply = oneshot::Sender<anyhow::Result<()>>;
+
+/// Handle used to ask a [`ReloadTask`] to reload the configuration
+#[derive(Clone, Debug)]
+pub struct Reloader {
+    requests: mpsc::UnboundedSender<Option<ReloadReply>>,
+}
+
+impl Reloader {
+    /// Creates a handle along with the task applying reloads to `focus_events` and publishing
+    /// changed monitor selectors to `requested_monitors` and changed actions to `actions`
+    #[must_use]
+    pub fn new(
+        source: ConfigSource,
+        settings: DaemonSettings,
+        focus_events: FocusHistory,
+        requested_monitors: watch::Sender<SortedDistinctVec<MonitorSelector>>,
+        actions: watch::Sender<ActionsConfig>,
+    ) -> (Self, ReloadTask) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        (
+            Self { requests: sender },
+            ReloadTask {
+                requests: receiver,
+                source,
+                settings,
+                focus_events,
+                requested_monitors,
+                actions,
+            },
+        )
+    }
+
+    /// Queues a reload without waiting for its outcome
+    pub(crate) fn trigger(&self) {
+        if self.requests.send(None).is_err() {
+            error!("Configuration reloader is no longer running");
+        }
+    }
+
+    pub(crate) async fn reload(&self) -> anyhow::Result<()> {
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .send(Some(reply))
+            .map_err(|_| anyhow::anyhow!("Configuration reloader is no longer running"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Configuration reloader is no longer running"))?
+    }
+}
+
+#[derive(Debug)]
+pub struct ReloadTask {
+    requests: mpsc::UnboundedReceiver<Option<ReloadReply>>,
+    source: ConfigSource,
+    settings: DaemonSettings,
+    focus_events: FocusHistory,
+    requested_monitors: watch::Sender<SortedDistinctVec<MonitorSelector>>,
+    actions: watch::Sender<ActionsConfig>,
+}
+
+impl ReloadTask {
+    /// Reloads the configuration on SIGHUP and on every request, one reload at a time
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        let mut sighup = signal(SignalKind::hangup())?;
+
+        loop {
+            let reply = tokio::select! {
+                request = self.requests.recv() => match request {
+                    Some(reply) => reply,
+                    None => return Ok(()),
+                },
+                _ = sighup.recv() => {
+                    info!("Received SIGHUP");
+                    None
+                }
+            };
+
+            systemd::notify_reloading();
+            let result = self.reload().await;
+            systemd::notify_ready();
+            if let Err(err) = &result {
+                error!("Failed to reload configuration: {err:#}");
+            }
+            if let Some(reply) = reply {
+                let _ = reply.send(result);
+            }
+        }
+    }
+
+    async fn reload(&mut self) -> anyhow::Result<()> {
+        let settings = self.source.settings()?;
+        let requested_monitors = SortedDistinctVec::new(settings.requested_monitors.clone());
+
+        self.focus_events
+            .reconfigure(
+                settings.history_size,
+                requested_monitors.clone(),
+                settings.window_filters.clone(),
+            )
+            .await?;
+
+        // The socket path is derived from the selectors, so the listener rebinds when they change
+        self.requested_monitors.send_if_modified(|current| {
+            let changed = current.get() != requested_monitors.get();
+            if changed {
+                *current = requested_monitors;
+            }
+            changed
+        });
+        self.actions.send_if_modified(|current| {
+            let changed = *current != settings.actions;
+            if changed {
+                current.clone_from(&settings.actions);
+            }
+            changed
+        });
+
+        if settings.reconcile_interval != self.settings.reconcile_interval
+            || settings.allowed_uids != self.settings.allowed_uids
+            || settings.dbus != self.settings.dbus
+            || settings.marks_path != self.settings.marks_path
+        {
+            warn!(
+                "Changes to reconcile-interval, allow-uids, dbus and persist-marks take effect when the daemon restarts"
+            );
+        }
+
+        info!(
+            "Reloaded configuration; history size {}, monitors {:?}",
+            settings.history_size,
+            settings
+                .requested_monitors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        self.settings = settings;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use clap::ValueEnum;
+use hyprland::{
+    data::{Monitor, Monitors, WorkspaceBasic},
+    shared::HyprData,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::window_cache::WindowCache;
+
+/// Windows that `focus next`/`prev` stop on, relative to where the command runs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Scope {
+    /// Windows on the focused workspace
+    Workspace,
+    /// Windows on the focused monitor
+    Monitor,
+    /// Windows on a workspace shown on any monitor
+    Visible,
+    /// Every window in the history
+    #[default]
+    All,
+}
+
+/// A [`Scope`] resolved against the workspaces and monitors at the time of the command
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScopeLocation {
+    Workspaces(Vec<String>),
+    Monitor(String),
+}
+
+/// Workspace a monitor shows in front: its special workspace when one is open
+pub(crate) fn shown_workspace(monitor: &Monitor) -> &WorkspaceBasic {
+    if monitor.special_workspace.name.is_empty() {
+        &monitor.active_workspace
+    } else {
+        &monitor.special_workspace
+    }
+}
+
+impl Scope {
+    /// Resolves the scope against `current_monitor`, or every monitor for [`Scope::Visible`].
+    /// [`Scope::All`] has no location to resolve.
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn resolve(
+        self,
+        current_monitor: &Monitor,
+    ) -> hyprland::Result<Option<ScopeLocation>> {
+        Ok(match self {
+            Self::Workspace => Some(ScopeLocation::Workspaces(vec![
+                shown_workspace(current_monitor).name.clone(),
+            ])),
+            Self::Monitor => Some(ScopeLocation::Monitor(current_monitor.name.clone())),
+            Self::Visible => {
+                let monitors = Monitors::get_async().await?;
+                let workspaces = monitors
+                    .iter()
+                    .flat_map(|monitor| {
+                        [
+                            &monitor.active_workspace.name,
+                            &monitor.special_workspace.name,
+                        ]
+                    })
+                    .filter(|workspace| !workspace.is_empty())
+                    .cloned()
+                    .collect();
+                Some(ScopeLocation::Workspaces(workspaces))
+            }
+            Self::All => None,
+        })
+    }
+}
+
+impl ScopeLocation {
+    /// Whether the window at `address` is currently inside this location; windows the cache
+    /// can't place are outside of it
+    #[must_use]
+    pub fn contains(&self, window_cache: &WindowCache, address: &str) -> bool {
+        match self {
+            Self::Workspaces(workspaces) => window_cache
+                .window_properties(address)
+                .is_some_and(|properties| workspaces.contains(&properties.workspace)),
+            Self::Monitor(monitor) => window_cache.window_monitor(address) == Some(monitor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScopeLocation;
+    use crate::window_cache::WindowCache;
+
+    #[test]
+    fn locations_follow_windows_to_their_current_workspace() {
+        let mut window_cache = WindowCache::default();
+        window_cache.workspace_moved("1".to_string(), "DP-1".to_string());
+        window_cache.workspace_moved("2".to_string(), "DP-1".to_string());
+        window_cache.window_opened(
+            "0x1".to_string(),
+            "1".to_string(),
+            "kitty".to_string(),
+            "zsh".to_string(),
+        );
+        let workspace = ScopeLocation::Workspaces(vec!["1".to_string()]);
+        let monitor = ScopeLocation::Monitor("DP-1".to_string());
+
+        assert!(workspace.contains(&window_cache, "0x1"));
+        assert!(monitor.contains(&window_cache, "0x1"));
+        assert!(!monitor.contains(&window_cache, "0x2"));
+
+        window_cache.window_moved("0x1".to_string(), "2".to_string(), 2);
+
+        assert!(!workspace.contains(&window_cache, "0x1"));
+        assert!(monitor.contains(&window_cache, "0x1"));
+    }
+}
@@ -0,0 +1,35 @@
+use log::info;
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::watch,
+};
+
+/// Resolves once the daemon has been asked to stop by SIGTERM or SIGINT
+#[derive(Clone, Debug)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    #[allow(clippy::missing_errors_doc)]
+    pub fn on_signals() -> anyhow::Result<Self> {
+        let mut sigterm = signal(SignalKind::terminate())?;
+        let mut sigint = signal(SignalKind::interrupt())?;
+        let (sender, receiver) = watch::channel(false);
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = sigterm.recv() => info!("Received SIGTERM"),
+                _ = sigint.recv() => info!("Received SIGINT"),
+            }
+            let _ = sender.send(true);
+        });
+
+        Ok(Self(receiver))
+    }
+
+    pub async fn requested(&mut self) {
+        if self.0.wait_for(|requested| *requested).await.is_err() {
+            // The signal task is gone without requesting a shutdown, so one never will be
+            std::future::pending::<()>().await;
+        }
+    }
+}
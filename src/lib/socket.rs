@@ -1,63 +1,237 @@
-use std::{ffi::OsStr, os::unix::fs::FileTypeExt, path::Path};
+use std::{
+    ffi::OsStr,
+    fs::Permissions,
+    num::NonZeroUsize,
+    os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
+    path::Path,
+};
 
 use anyhow::Context;
+use chrono::NaiveDateTime;
 use hyprland::{
-    data::Monitor,
-    dispatch::{Dispatch, DispatchType, WindowIdentifier},
-    shared::{Address, HyprDataActive},
+    data::{Client, Monitor, Monitors},
+    dispatch::{Dispatch, DispatchType},
+    shared::{HyprData, HyprDataActive, HyprDataActiveOptional},
 };
 use log::{debug, error, info, warn};
+use nix::{
+    sys::stat::{Mode, umask},
+    unistd::Uid,
+};
 use serde::{Deserialize, Serialize};
 use tokio::{
     fs::{self, DirEntry},
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::{UnixListener, UnixStream},
+    net::{UnixListener, UnixStream, unix::OwnedWriteHalf},
+    sync::broadcast::error::RecvError,
+    task::JoinSet,
 };
 
 use crate::{
-    cli::{FocusCommand, FocusCommandArgs},
-    types::{FocusEvents, HyprEvents, SharedEventHistory, SortedDistinctVec, WindowEvent},
+    cli::{FocusCommand, JumpArgs, ListSort, MarkCommand},
+    config::Config,
+    event_history::EntryStatus,
+    focus_action::FocusAction,
+    focus_history::FocusHistory,
+    monitor_selector::{MonitorSelector, selects_monitor},
+    reload::Reloader,
+    scope::ScopeLocation,
+    shutdown::Shutdown,
+    systemd,
+    types::{FocusEvents, HyprEvents, SortedDistinctVec},
+    window_cache::WindowProperties,
+    window_filter::ValuePattern,
 };
 
 const FOCUS_SOCKET_PATH_ALL: &str = "/tmp/hyprhist_focus.sock";
 const FOCUS_SOCKET_PREFIX: &str = "hyprhist_focus";
 const TMP_PATH: &str = "/tmp";
+const OWNER_ONLY_SOCKET_MODE: u32 = 0o600;
+const ALLOWLISTED_SOCKET_MODE: u32 = 0o666;
+// `sun_path` holds 108 bytes, including the terminating NUL
+const MAX_SOCKET_PATH_LEN: usize = 107;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
-enum SocketInstruction {
-    Next,
-    Prev,
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) enum SocketInstruction {
+    Next {
+        count: NonZeroUsize,
+        class: Option<ValuePattern>,
+        scope: Option<ScopeLocation>,
+        #[serde(default)]
+        include_inactive: bool,
+        #[serde(default)]
+        action: FocusAction,
+    },
+    Prev {
+        count: NonZeroUsize,
+        class: Option<ValuePattern>,
+        scope: Option<ScopeLocation>,
+        #[serde(default)]
+        include_inactive: bool,
+        #[serde(default)]
+        action: FocusAction,
+    },
+    Goto {
+        index: usize,
+        #[serde(default)]
+        action: FocusAction,
+    },
+    List,
+    Subscribe,
+    Reload,
+    SetMark {
+        mark: char,
+        address: String,
+    },
+    JumpToMark {
+        mark: char,
+        #[serde(default)]
+        action: FocusAction,
+    },
+    ListMarks,
+    ClearMark {
+        mark: char,
+    },
+    Jump {
+        class: ValuePattern,
+        exec: Option<String>,
+        #[serde(default)]
+        action: FocusAction,
+    },
+    Last {
+        #[serde(default)]
+        action: FocusAction,
+    },
+    Frecent {
+        rank: NonZeroUsize,
+        #[serde(default)]
+        action: FocusAction,
+    },
+    ListByFrecency,
 }
 
-impl From<&FocusCommand> for SocketInstruction {
-    fn from(value: &FocusCommand) -> Self {
-        match value {
-            FocusCommand::Next(_) => SocketInstruction::Next,
-            FocusCommand::Prev(_) => SocketInstruction::Prev,
-        }
-    }
+/// Answer to a [`SocketInstruction::Reload`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum ReloadResponse {
+    Ok,
+    Error { message: String },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct ListedWindow {
+    pub index: usize,
+    pub address: String,
+    pub monitor: Option<String>,
+    pub time: NaiveDateTime,
+    #[serde(flatten)]
+    pub properties: WindowProperties,
+    pub status: EntryStatus,
+    pub current: bool,
+    /// Only set when listing by frecency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frecency: Option<f64>,
 }
 
 impl SocketInstruction {
-    fn as_str(self) -> &'static str {
+    /// Instruction sent for `command`, traversing within `scope` and focusing with `action`
+    /// where the command takes them
+    fn new(command: &FocusCommand, scope: Option<ScopeLocation>, action: FocusAction) -> Self {
+        match command {
+            FocusCommand::Next(args) => SocketInstruction::Next {
+                count: args.steps(),
+                class: args.class.clone(),
+                scope,
+                include_inactive: args.include_inactive,
+                action,
+            },
+            FocusCommand::Prev(args) => SocketInstruction::Prev {
+                count: args.steps(),
+                class: args.class.clone(),
+                scope,
+                include_inactive: args.include_inactive,
+                action,
+            },
+            FocusCommand::Goto(args) => SocketInstruction::Goto {
+                index: args.index,
+                action,
+            },
+            FocusCommand::Last(_) => SocketInstruction::Last { action },
+            FocusCommand::Frecent(args) => SocketInstruction::Frecent {
+                rank: args.rank,
+                action,
+            },
+            FocusCommand::List(args) => match args.sort {
+                ListSort::History => SocketInstruction::List,
+                ListSort::Frecency => SocketInstruction::ListByFrecency,
+            },
+            FocusCommand::Watch(_) => SocketInstruction::Subscribe,
+            FocusCommand::Reload(_) => SocketInstruction::Reload,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Next { .. } => "next",
+            Self::Prev { .. } => "prev",
+            Self::Goto { .. } => "goto",
+            Self::List => "list",
+            Self::Subscribe => "subscribe",
+            Self::Reload => "reload",
+            Self::SetMark { .. } => "set-mark",
+            Self::JumpToMark { .. } => "jump-to-mark",
+            Self::ListMarks => "list-marks",
+            Self::ClearMark { .. } => "clear-mark",
+            Self::Jump { .. } => "jump",
+            Self::Last { .. } => "last",
+            Self::Frecent { .. } => "frecent",
+            Self::ListByFrecency => "list-by-frecency",
+        }
+    }
+
+    fn focus_action(&self) -> FocusAction {
         match self {
-            Self::Next => "next",
-            Self::Prev => "prev",
+            Self::Next { action, .. }
+            | Self::Prev { action, .. }
+            | Self::Goto { action, .. }
+            | Self::JumpToMark { action, .. }
+            | Self::Jump { action, .. }
+            | Self::Last { action }
+            | Self::Frecent { action, .. } => *action,
+            Self::List
+            | Self::Subscribe
+            | Self::Reload
+            | Self::SetMark { .. }
+            | Self::ListMarks
+            | Self::ClearMark { .. }
+            | Self::ListByFrecency => FocusAction::default(),
         }
     }
 }
 
-fn generate_socket_path(input: &SortedDistinctVec<String>) -> String {
+fn generate_socket_path(input: &SortedDistinctVec<MonitorSelector>) -> String {
     if input.get().is_empty() {
         FOCUS_SOCKET_PATH_ALL.to_string()
     } else {
+        let selectors: Vec<String> = input.iter().map(ToString::to_string).collect();
         format!(
             "{TMP_PATH}/{FOCUS_SOCKET_PREFIX}::{}.sock",
-            input.get().join("::")
+            selectors.join("::")
         )
     }
 }
 
+fn check_socket_path_length(socket_path: &str) -> anyhow::Result<()> {
+    if socket_path.len() > MAX_SOCKET_PATH_LEN {
+        anyhow::bail!(
+            "Socket path {socket_path} is {} bytes, longer than the {MAX_SOCKET_PATH_LEN} a unix \
+             socket allows; use fewer or shorter monitor selectors",
+            socket_path.len()
+        );
+    }
+    Ok(())
+}
+
 async fn is_focus_socket_file(entry: &DirEntry) -> anyhow::Result<bool> {
     Ok(entry.file_type().await?.is_socket()
         && entry.path().extension() == Some(OsStr::new("sock"))
@@ -68,62 +242,157 @@ async fn is_focus_socket_file(entry: &DirEntry) -> anyhow::Result<bool> {
             .starts_with(FOCUS_SOCKET_PREFIX))
 }
 
+/// Monitor selectors of the daemon owning a focus socket, or `None` for the all monitor socket
+fn focus_socket_monitors(file_name: &str) -> Option<Vec<&str>> {
+    file_name
+        .strip_suffix(".sock")?
+        .strip_prefix(&format!("{FOCUS_SOCKET_PREFIX}::"))
+        .map(|monitor_strs| monitor_strs.split("::").collect())
+}
+
+/// Parsed monitor selectors of the daemon owning a focus socket, or `None` for the all monitor
+/// socket
+fn focus_socket_selectors(file_name: &str) -> Option<Result<Vec<MonitorSelector>, String>> {
+    focus_socket_monitors(file_name)
+        .map(|selectors| selectors.into_iter().map(str::parse).collect())
+}
+
+/// Whether the daemon owning a focus socket tracks `monitor`; sockets with selectors that no
+/// longer parse never match
+fn focus_socket_selects_monitor(file_name: &str, monitor: &Monitor) -> bool {
+    matches!(
+        focus_socket_selectors(file_name),
+        Some(Ok(selectors)) if selects_monitor(&selectors, &monitor.name, &monitor.description)
+    )
+}
+
+/// Routes to the daemon tracking `monitor`. Should several, picks the first socket by name so
+/// every client agrees
+async fn find_socket_for_monitor(monitor: &Monitor) -> anyhow::Result<String> {
+    let mut directory = fs::read_dir(TMP_PATH).await?;
+    let mut matching_sockets = Vec::new();
+
+    while let Some(entry) = directory.next_entry().await? {
+        if is_focus_socket_file(&entry).await? {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if focus_socket_selects_monitor(&file_name, monitor) {
+                matching_sockets.push(entry.path().to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    matching_sockets.sort_unstable();
+    if matching_sockets.len() > 1 {
+        warn!(
+            "Several daemons track monitor {}, using {}",
+            monitor.name, matching_sockets[0]
+        );
+    }
+
+    Ok(matching_sockets
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| FOCUS_SOCKET_PATH_ALL.to_string()))
+}
+
+/// Why the focus socket `file_name` conflicts with a daemon for `requested_monitors`, given the
+/// `(name, description)` pairs of the connected monitors, or `None` if both can run side by side
+fn focus_socket_conflict(
+    file_name: &str,
+    requested_monitors: &[MonitorSelector],
+    monitors: &[(&str, &str)],
+) -> Option<String> {
+    let is_all_socket = Path::new(TMP_PATH).join(file_name) == Path::new(FOCUS_SOCKET_PATH_ALL);
+
+    if requested_monitors.is_empty() {
+        // Since all available monitors are being tracked, any existing selective daemon sockets
+        // will conflict
+        return (!is_all_socket).then(|| "tracking a subset of monitors".to_string());
+    }
+
+    match focus_socket_selectors(file_name) {
+        // If there are specific monitor requested in this daemon, the socket tracking every
+        // monitor will conflict
+        None => is_all_socket.then(|| "tracking all monitors".to_string()),
+        // Its monitors can't be known, so it can't be routed to either
+        Some(Err(err)) => Some(format!("with invalid monitor selectors ({err})")),
+        Some(Ok(selectors)) => monitors
+            .iter()
+            .find(|(name, description)| {
+                selects_monitor(requested_monitors, name, description)
+                    && selects_monitor(&selectors, name, description)
+            })
+            .map(|(name, _)| format!("with overlapping monitor '{name}'")),
+    }
+}
+
 async fn remove_overlapping_focus_sockets(
-    requested_monitors: &SortedDistinctVec<String>,
+    requested_monitors: &SortedDistinctVec<MonitorSelector>,
 ) -> anyhow::Result<()> {
+    let monitors: Vec<Monitor> = if requested_monitors.get().is_empty() {
+        Vec::new()
+    } else {
+        Monitors::get_async().await?.into_iter().collect()
+    };
+    let monitors: Vec<(&str, &str)> = monitors
+        .iter()
+        .map(|monitor| (monitor.name.as_str(), monitor.description.as_str()))
+        .collect();
     let mut directory = fs::read_dir(TMP_PATH).await?;
 
-    if requested_monitors.get().is_empty() {
-        // Since all available monitors are being tracked, any existing selective daemon sockets
-        // will conflict and need to be removed
-        while let Some(entry) = directory.next_entry().await? {
-            if is_focus_socket_file(&entry).await?
-                && entry.path().to_string_lossy().into_owned() != FOCUS_SOCKET_PATH_ALL
+    while let Some(entry) = directory.next_entry().await? {
+        if is_focus_socket_file(&entry).await? {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+
+            if let Some(conflict) =
+                focus_socket_conflict(&file_name, requested_monitors.get(), &monitors)
             {
                 warn!(
-                    "Removing conflicting socket at path '{}'",
+                    "Removing conflicting socket {conflict} at path '{}'",
                     entry.path().to_string_lossy()
                 );
                 fs::remove_file(entry.path()).await?;
             }
         }
-    } else {
-        while let Some(entry) = directory.next_entry().await? {
-            if is_focus_socket_file(&entry).await? {
-                let file_name = entry.file_name().to_string_lossy().into_owned();
-                let file_name_without_ext = file_name
-                    .strip_suffix(".sock")
-                    .expect("Expected file to end with sock extension");
-
-                if let Some(monitor_strs) =
-                    file_name_without_ext.strip_prefix(&format!("{FOCUS_SOCKET_PREFIX}::"))
-                {
-                    for monitor_str in monitor_strs.split("::") {
-                        if requested_monitors.get().contains(&monitor_str.to_owned()) {
-                            warn!(
-                                "Removing conflicting socket with overlapping monitor '{}' at path '{}'",
-                                monitor_str,
-                                entry.path().to_string_lossy()
-                            );
-                            fs::remove_file(entry.path()).await?;
-                        }
-                    }
-                }
+    }
+
+    Ok(())
+}
+
+/// Removes the socket file this daemon bound when dropped, unless another daemon has since
+/// replaced it with its own socket at the same path
+struct BoundSocket {
+    path: String,
+    inode: u64,
+}
+
+impl BoundSocket {
+    async fn new(path: &str) -> anyhow::Result<Self> {
+        let inode = fs::metadata(path)
+            .await
+            .with_context(|| format!("Failed to read metadata of {path}"))?
+            .ino();
+
+        Ok(Self {
+            path: path.to_string(),
+            inode,
+        })
+    }
+}
 
-                // If there are specific monitor requested in this daemon any existing
-                // FOCUS_SOCKET_PATH_ALL will conflict
-                if entry.path().to_string_lossy().into_owned() == FOCUS_SOCKET_PATH_ALL {
-                    warn!(
-                        "Removing conflicting socket for all monitors at path '{}'",
-                        entry.path().to_string_lossy()
-                    );
-                    fs::remove_file(entry.path()).await?;
+impl Drop for BoundSocket {
+    fn drop(&mut self) {
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) if metadata.ino() == self.inode => {
+                if let Err(err) = std::fs::remove_file(&self.path) {
+                    error!("Failed to remove socket at {}: {err}", self.path);
+                } else {
+                    info!("Removed socket at {}", self.path);
                 }
             }
+            _ => debug!("Socket at {} is no longer owned by this daemon", self.path),
         }
     }
-
-    Ok(())
 }
 
 async fn cleanup_socket(path: &str) -> anyhow::Result<()> {
@@ -136,51 +405,215 @@ async fn cleanup_socket(path: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn navigate_focus_history(
-    instruction: SocketInstruction,
-    focus_events: SharedEventHistory<WindowEvent>,
+fn uid_is_allowed(peer_uid: u32, allowed_uids: &SortedDistinctVec<u32>) -> bool {
+    peer_uid == Uid::effective().as_raw() || allowed_uids.get().contains(&peer_uid)
+}
+
+/// Binds `socket_path` so the socket file is created owner only, instead of being connectable
+/// by anyone until its permissions are restricted
+fn bind_owner_only(socket_path: &str) -> std::io::Result<UnixListener> {
+    // The umask is process wide, but the bind doesn't yield, so nothing else on this runtime
+    // creates files in between
+    let previous_umask = umask(Mode::from_bits_truncate(0o777 & !OWNER_ONLY_SOCKET_MODE));
+    let listener = UnixListener::bind(socket_path);
+    umask(previous_umask);
+    listener
+}
+
+async fn restrict_socket_permissions(
+    socket_path: &str,
+    allowed_uids: &SortedDistinctVec<u32>,
 ) -> anyhow::Result<()> {
+    // Connecting to a unix socket requires write access to the socket file, so allowlisted
+    // users need the file opened up; peer credentials are then the only gate
+    let mode = if allowed_uids.get().is_empty() {
+        OWNER_ONLY_SOCKET_MODE
+    } else {
+        warn!(
+            "Uids {:?} are allowed, so {socket_path} is writable by every user and only the \
+             peer credentials of each connection are checked",
+            allowed_uids.get()
+        );
+        ALLOWLISTED_SOCKET_MODE
+    };
+
+    fs::set_permissions(socket_path, Permissions::from_mode(mode))
+        .await
+        .with_context(|| format!("Failed to set permissions on {socket_path}"))
+}
+
+/// Moves the history cursor and focuses the window at its new position, returning its address
+pub(crate) async fn navigate_focus_history(
+    instruction: SocketInstruction,
+    focus_events: &FocusHistory,
+) -> Option<String> {
     debug!("Recieved socked instruction of {instruction:?}");
 
-    let next_address = {
-        let mut history = focus_events.lock().await;
-        match instruction {
-            SocketInstruction::Next => history.forward().map(|e| e.address.clone()),
-            SocketInstruction::Prev => history.backward().map(|e| e.address.clone()),
+    let instruction_name = instruction.as_str();
+    let action = instruction.focus_action();
+    let launch_command = match &instruction {
+        SocketInstruction::Jump { exec, .. } => exec.clone(),
+        _ => None,
+    };
+    let next_address = match focus_events.navigate(instruction).await {
+        Ok(next_address) => next_address,
+        Err(err) => {
+            error!("Failed to move focus history cursor: {err}");
+            return None;
         }
     };
 
-    if let Some(addr) = next_address {
-        info!(
-            "Moved focus history cursor with {} (id {})",
-            instruction.as_str(),
-            addr
-        );
-        let _ = Dispatch::call_async(DispatchType::FocusWindow(WindowIdentifier::Address(
-            Address::new(addr),
-        )))
-        .await;
+    if let Some(addr) = &next_address {
+        info!("Moved focus history cursor with {instruction_name} (id {addr})",);
+        if let Err(err) = action.perform(addr, focus_events).await {
+            error!("Failed to {action:?} window {addr}: {err}");
+        }
+    } else if let Some(command) = launch_command {
+        info!("No window to {instruction_name} to; launching {command}");
+        if let Err(err) = Dispatch::call_async(DispatchType::Exec(&command)).await {
+            error!("Failed to launch {command}: {err}");
+        }
     } else {
-        info!(
-            "No focus history item available for {} request",
-            instruction.as_str()
-        );
+        info!("No focus history item available for {instruction_name} request");
     }
 
-    Ok(())
+    next_address
+}
+
+async fn write_focus_history(
+    mut writer: OwnedWriteHalf,
+    windows: Vec<ListedWindow>,
+) -> anyhow::Result<()> {
+    let mut payload = String::new();
+    for window in windows {
+        payload.push_str(&serde_json::to_string(&window)?);
+        payload.push('\n');
+    }
+
+    writer
+        .write_all(payload.as_bytes())
+        .await
+        .context("Failed to write focus history")
+}
+
+async fn write_marks(
+    mut writer: OwnedWriteHalf,
+    focus_events: &FocusHistory,
+) -> anyhow::Result<()> {
+    let mut payload = String::new();
+    for mark in focus_events.list_marks().await? {
+        payload.push_str(&serde_json::to_string(&mark)?);
+        payload.push('\n');
+    }
+
+    writer
+        .write_all(payload.as_bytes())
+        .await
+        .context("Failed to write marks")
+}
+
+async fn stream_history_changes(
+    mut writer: OwnedWriteHalf,
+    focus_events: &FocusHistory,
+    mut shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    let mut changes = focus_events.subscribe().await?;
+    info!("Focus history subscriber connected");
+
+    loop {
+        let received = tokio::select! {
+            received = changes.recv() => received,
+            () = shutdown.requested() => return Ok(()),
+        };
+
+        let change = match received {
+            Ok(change) => change,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Focus history subscriber lagged; {skipped} changes were dropped");
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        let mut payload = serde_json::to_string(&change)?;
+        payload.push('\n');
+
+        if let Err(err) = writer.write_all(payload.as_bytes()).await {
+            info!("Focus history subscriber disconnected: {err}");
+            return Ok(());
+        }
+    }
+}
+
+async fn write_reload_response(
+    mut writer: OwnedWriteHalf,
+    reloader: &Reloader,
+) -> anyhow::Result<()> {
+    let response = match reloader.reload().await {
+        Ok(()) => ReloadResponse::Ok,
+        Err(err) => ReloadResponse::Error {
+            message: format!("{err:#}"),
+        },
+    };
+
+    let mut payload = serde_json::to_string(&response)?;
+    payload.push('\n');
+
+    writer
+        .write_all(payload.as_bytes())
+        .await
+        .context("Failed to write reload response")
 }
 
 async fn handle_focus_stream(
     stream: UnixStream,
-    event_history: SharedEventHistory<WindowEvent>,
+    focus_events: FocusHistory,
+    reloader: Reloader,
+    mut shutdown: Shutdown,
 ) -> anyhow::Result<()> {
-    let mut reader = BufReader::new(stream);
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
-    while reader.read_line(&mut line).await? != 0 {
+    loop {
+        let bytes_read = tokio::select! {
+            bytes_read = reader.read_line(&mut line) => bytes_read?,
+            () = shutdown.requested() => return Ok(()),
+        };
+
+        if bytes_read == 0 {
+            break;
+        }
+
         let instruction: Option<SocketInstruction> = serde_json::from_str(line.trim())?;
-        if let Some(instruction) = instruction {
-            navigate_focus_history(instruction, event_history.clone()).await?;
+        match instruction {
+            Some(SocketInstruction::Subscribe) => {
+                return stream_history_changes(writer, &focus_events, shutdown).await;
+            }
+            Some(SocketInstruction::List) => {
+                return write_focus_history(writer, focus_events.list().await?).await;
+            }
+            Some(SocketInstruction::ListByFrecency) => {
+                return write_focus_history(writer, focus_events.list_by_frecency().await?).await;
+            }
+            Some(SocketInstruction::Reload) => {
+                return write_reload_response(writer, &reloader).await;
+            }
+            Some(SocketInstruction::ListMarks) => {
+                return write_marks(writer, &focus_events).await;
+            }
+            Some(SocketInstruction::SetMark { mark, address }) => {
+                info!("Setting mark '{mark}' on {address}");
+                focus_events.set_mark(mark, address);
+            }
+            Some(SocketInstruction::ClearMark { mark }) => {
+                info!("Clearing mark '{mark}'");
+                focus_events.clear_mark(mark);
+            }
+            Some(instruction) => {
+                navigate_focus_history(instruction, &focus_events).await;
+            }
+            None => {}
         }
 
         line.clear();
@@ -189,49 +622,249 @@ async fn handle_focus_stream(
     Ok(())
 }
 
+async fn bind_focus_socket(
+    requested_monitors: &SortedDistinctVec<MonitorSelector>,
+    allowed_uids: &SortedDistinctVec<u32>,
+) -> anyhow::Result<(UnixListener, BoundSocket)> {
+    let socket_path = generate_socket_path(requested_monitors);
+    check_socket_path_length(&socket_path)?;
+    cleanup_socket(&socket_path).await?;
+    remove_overlapping_focus_sockets(requested_monitors).await?;
+    let listener = bind_owner_only(&socket_path)
+        .with_context(|| format!("Failed to bind to {socket_path}"))?;
+    let bound_socket = BoundSocket::new(&socket_path).await?;
+    restrict_socket_permissions(&socket_path, allowed_uids).await?;
+
+    info!("Listening for focus navigation on {socket_path}");
+
+    Ok((listener, bound_socket))
+}
+
+/// Adopts a listener passed by socket activation. The service manager owns its socket file, so
+/// it's never moved, restricted or removed by the daemon
+fn adopt_activated_socket(
+    listener: std::os::unix::net::UnixListener,
+    requested_monitors: &SortedDistinctVec<MonitorSelector>,
+) -> anyhow::Result<UnixListener> {
+    let listener = UnixListener::from_std(listener)?;
+    let socket_path = generate_socket_path(requested_monitors);
+    let local_addr = listener.local_addr()?;
+
+    match local_addr.as_pathname() {
+        Some(path) if path == Path::new(&socket_path) => {}
+        Some(path) => warn!(
+            "Activated socket {} doesn't match {socket_path}, where focus commands are sent",
+            path.display()
+        ),
+        None => {
+            warn!("Activated socket isn't bound to {socket_path}, where focus commands are sent");
+        }
+    }
+
+    info!("Listening for focus navigation on activated socket {socket_path}");
+
+    Ok(listener)
+}
+
+/// Serves focus commands until `shutdown` is requested, then stops accepting connections,
+/// waits for in-flight commands to finish and removes the socket. The socket is bound again
+/// whenever a reload changes the monitor selectors it's named after, unless it was passed in
+/// by socket activation
 #[allow(clippy::missing_errors_doc)]
-pub async fn listen(hypr_events: HyprEvents) -> anyhow::Result<()> {
+pub async fn listen(
+    hypr_events: HyprEvents,
+    allowed_uids: &'static SortedDistinctVec<u32>,
+    activated_listener: Option<std::os::unix::net::UnixListener>,
+    mut shutdown: Shutdown,
+) -> anyhow::Result<()> {
     match hypr_events {
         HyprEvents::Focus(FocusEvents {
             focus_events,
-            requested_monitors,
+            reloader,
+            mut requested_monitors,
+            ..
         }) => {
-            let socket_path = generate_socket_path(requested_monitors);
-            cleanup_socket(&socket_path).await?;
-            remove_overlapping_focus_sockets(requested_monitors).await?;
-            let listener = UnixListener::bind(&socket_path)
-                .with_context(|| format!("Failed to bind to {socket_path}"))?;
+            let selectors = requested_monitors.borrow_and_update().clone();
+            let (mut listener, mut bound_socket) = if let Some(activated_listener) =
+                activated_listener
+            {
+                (
+                    adopt_activated_socket(activated_listener, &selectors)?,
+                    None,
+                )
+            } else {
+                let (listener, bound_socket) = bind_focus_socket(&selectors, allowed_uids).await?;
+                (listener, Some(bound_socket))
+            };
+            systemd::notify_ready();
 
-            info!("Listening for focus navigation on {socket_path}");
+            let mut connections = JoinSet::new();
 
             loop {
-                let (stream, _) = listener.accept().await?;
+                let (stream, _) = tokio::select! {
+                    accepted = listener.accept() => accepted?,
+                    Ok(()) = requested_monitors.changed() => {
+                        let selectors = requested_monitors.borrow_and_update().clone();
+                        if bound_socket.is_none() {
+                            warn!("Monitors changed, but the activated socket is kept until restart");
+                        } else {
+                            match bind_focus_socket(&selectors, allowed_uids).await {
+                                Ok((rebound, rebound_socket)) => {
+                                    listener = rebound;
+                                    bound_socket = Some(rebound_socket);
+                                }
+                                Err(err) => error!("Failed to bind focus socket after reload: {err:#}"),
+                            }
+                        }
+                        continue;
+                    }
+                    () = shutdown.requested() => break,
+                };
+
+                // Reap finished connections so the set doesn't grow for the daemon's lifetime
+                while connections.try_join_next().is_some() {}
+
+                match stream.peer_cred() {
+                    Ok(cred) if uid_is_allowed(cred.uid(), allowed_uids) => {}
+                    Ok(cred) => {
+                        warn!("Rejected focus socket connection from uid {}", cred.uid());
+                        continue;
+                    }
+                    Err(err) => {
+                        error!("Rejected focus socket connection without peer credentials: {err}");
+                        continue;
+                    }
+                }
+
                 let focus_events = focus_events.clone();
+                let reloader = reloader.clone();
+                let shutdown = shutdown.clone();
 
-                tokio::spawn(async move {
-                    if let Err(err) = handle_focus_stream(stream, focus_events).await {
+                connections.spawn(async move {
+                    if let Err(err) =
+                        handle_focus_stream(stream, focus_events, reloader, shutdown).await
+                    {
                         error!("Failed handling focus socket request: {err:?}");
                     }
                 });
             }
+
+            systemd::notify_stopping();
+            info!("Stopped accepting focus socket connections");
+            drop(listener);
+
+            while connections.join_next().await.is_some() {}
+            drop(bound_socket);
+
+            Ok(())
+        }
+    }
+}
+
+async fn resolve_socket_path(
+    requested_monitors: &[MonitorSelector],
+    current_monitor: &Monitor,
+) -> anyhow::Result<String> {
+    if requested_monitors.is_empty() {
+        // Route to whichever daemon tracks the active monitor, falling back to the daemon
+        // tracking every monitor
+        return find_socket_for_monitor(current_monitor).await;
+    }
+
+    let socket_path = generate_socket_path(&SortedDistinctVec::new(requested_monitors.to_vec()));
+    check_socket_path_length(&socket_path)?;
+    Ok(socket_path)
+}
+
+/// Sends an instruction that the daemon answers over the same connection
+async fn request_focus_socket(
+    requested_monitors: &[MonitorSelector],
+    instruction: SocketInstruction,
+) -> anyhow::Result<UnixStream> {
+    let current_monitor = Monitor::get_active_async().await?;
+    let socket_path = resolve_socket_path(requested_monitors, &current_monitor).await?;
+
+    let mut stream = UnixStream::connect(&socket_path).await.context(format!(
+        "Failed to connect to focus socket at {}",
+        &socket_path
+    ))?;
+
+    let mut payload = serde_json::to_string(&instruction)?;
+    payload.push('\n');
+
+    stream
+        .write_all(payload.as_bytes())
+        .await
+        .with_context(|| format!("Failed to send {} request", instruction.as_str()))?;
+
+    Ok(stream)
+}
+
+/// Sends an instruction that the daemon answers with newline-delimited JSON, and copies the
+/// response to stdout until the daemon closes the connection
+async fn print_focus_socket_response(
+    requested_monitors: &[MonitorSelector],
+    instruction: SocketInstruction,
+) -> anyhow::Result<()> {
+    let stream = request_focus_socket(requested_monitors, instruction).await?;
+
+    let mut lines = BufReader::new(stream).lines();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(line) = lines.next_line().await? {
+        stdout.write_all(line.as_bytes()).await?;
+        stdout.write_all(b"\n").await?;
+        stdout.flush().await?;
+    }
+
+    Ok(())
+}
+
+async fn request_reload(requested_monitors: &[MonitorSelector]) -> anyhow::Result<()> {
+    let stream = request_focus_socket(requested_monitors, SocketInstruction::Reload).await?;
+
+    let response = BufReader::new(stream)
+        .lines()
+        .next_line()
+        .await?
+        .context("Daemon closed the connection without answering the reload request")?;
+
+    match serde_json::from_str(&response)? {
+        ReloadResponse::Ok => {
+            info!("Reloaded daemon configuration");
+            Ok(())
+        }
+        ReloadResponse::Error { message } => {
+            anyhow::bail!("Daemon failed to reload its configuration: {message}")
         }
     }
 }
 
 #[allow(clippy::missing_errors_doc)]
-pub async fn send_focus_command(command: &'static FocusCommand) -> anyhow::Result<()> {
-    let requested_monitors = match command {
-        FocusCommand::Next(FocusCommandArgs {
-            requested_monitors: monitors,
-        })
-        | FocusCommand::Prev(FocusCommandArgs {
-            requested_monitors: monitors,
-        }) => monitors,
-    };
+pub async fn send_focus_command(
+    command: &'static FocusCommand,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let requested_monitors = &config.focus_monitors(command.args())?;
+
+    if let FocusCommand::Watch(_) | FocusCommand::List(_) = command {
+        let instruction = SocketInstruction::new(command, None, FocusAction::default());
+        return print_focus_socket_response(requested_monitors, instruction).await;
+    }
+
+    if let FocusCommand::Reload(_) = command {
+        return request_reload(requested_monitors).await;
+    }
 
     let current_monitor = Monitor::get_active_async().await?;
 
-    if !requested_monitors.is_empty() && !requested_monitors.contains(&current_monitor.name) {
+    if !requested_monitors.is_empty()
+        && !selects_monitor(
+            requested_monitors,
+            &current_monitor.name,
+            &current_monitor.description,
+        )
+    {
         info!(
             "Ignoring focus command on untracked monitor: {}",
             current_monitor.name
@@ -239,14 +872,20 @@ pub async fn send_focus_command(command: &'static FocusCommand) -> anyhow::Resul
         return Ok(());
     }
 
-    let socket_path = generate_socket_path(&SortedDistinctVec::new(requested_monitors.clone()));
+    let socket_path = resolve_socket_path(requested_monitors, &current_monitor).await?;
 
     let mut stream = UnixStream::connect(&socket_path).await.context(format!(
         "Failed to connect to focus socket at {}",
         &socket_path
     ))?;
 
-    let payload: SocketInstruction = command.into();
+    let scope = match command {
+        FocusCommand::Next(args) | FocusCommand::Prev(args) => {
+            args.scope.resolve(&current_monitor).await?
+        }
+        _ => None,
+    };
+    let payload = SocketInstruction::new(command, scope, config.focus_action(command));
 
     stream
         .write_all(serde_json::to_string(&payload)?.as_bytes())
@@ -255,3 +894,152 @@ pub async fn send_focus_command(command: &'static FocusCommand) -> anyhow::Resul
 
     Ok(())
 }
+
+#[allow(clippy::missing_errors_doc)]
+pub async fn send_mark_command(command: &MarkCommand, config: &Config) -> anyhow::Result<()> {
+    let requested_monitors = &config.focus_monitors(command.args())?;
+
+    let instruction = match command {
+        MarkCommand::Set(args) => {
+            let focused = Client::get_active_async()
+                .await?
+                .context("No window is focused")?;
+            SocketInstruction::SetMark {
+                mark: args.mark,
+                address: focused.address.to_string(),
+            }
+        }
+        MarkCommand::Jump(args) => SocketInstruction::JumpToMark {
+            mark: args.mark,
+            action: config.mark_action(args),
+        },
+        MarkCommand::Clear(args) => SocketInstruction::ClearMark { mark: args.mark },
+        MarkCommand::List(_) => {
+            return print_focus_socket_response(requested_monitors, SocketInstruction::ListMarks)
+                .await;
+        }
+    };
+
+    request_focus_socket(requested_monitors, instruction).await?;
+    Ok(())
+}
+
+#[allow(clippy::missing_errors_doc)]
+pub async fn send_jump_command(args: &JumpArgs, config: &Config) -> anyhow::Result<()> {
+    let requested_monitors = &config.focus_monitors(&args.focus)?;
+    let instruction = SocketInstruction::Jump {
+        class: args.class.clone(),
+        exec: args.exec.clone(),
+        action: config.jump_action(args),
+    };
+
+    request_focus_socket(requested_monitors, instruction).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::{
+        MAX_SOCKET_PATH_LEN, OWNER_ONLY_SOCKET_MODE, bind_owner_only, check_socket_path_length,
+        focus_socket_conflict, focus_socket_monitors,
+    };
+    use crate::monitor_selector::MonitorSelector;
+
+    const MONITORS: [(&str, &str); 2] = [
+        ("DP-1", "Dell Inc. DELL U2720Q 1234"),
+        ("HDMI-A-1", "LG Electronics 27GL850 5678"),
+    ];
+
+    fn selectors(sources: &[&str]) -> Vec<MonitorSelector> {
+        sources
+            .iter()
+            .map(|source| source.parse().expect("valid selector"))
+            .collect()
+    }
+
+    #[test]
+    fn parses_monitors_from_selective_socket_name() {
+        assert_eq!(
+            focus_socket_monitors("hyprhist_focus::DP-1::HDMI-A-1.sock"),
+            Some(vec!["DP-1", "HDMI-A-1"])
+        );
+    }
+
+    #[test]
+    fn all_monitor_socket_has_no_monitors() {
+        assert_eq!(focus_socket_monitors("hyprhist_focus.sock"), None);
+    }
+
+    #[test]
+    fn sockets_sharing_a_connected_monitor_conflict() {
+        let requested = selectors(&["desc:Dell*"]);
+
+        assert_eq!(
+            focus_socket_conflict("hyprhist_focus::DP-1.sock", &requested, &MONITORS),
+            Some("with overlapping monitor 'DP-1'".to_string())
+        );
+        assert_eq!(
+            focus_socket_conflict("hyprhist_focus::re:^HDMI.sock", &requested, &MONITORS),
+            None
+        );
+        assert_eq!(
+            focus_socket_conflict("hyprhist_focus::!HDMI-A-1.sock", &requested, &MONITORS),
+            Some("with overlapping monitor 'DP-1'".to_string())
+        );
+    }
+
+    #[test]
+    fn all_monitor_socket_conflicts_with_selective_daemons() {
+        assert_eq!(
+            focus_socket_conflict("hyprhist_focus.sock", &selectors(&["DP-1"]), &MONITORS),
+            Some("tracking all monitors".to_string())
+        );
+        assert_eq!(
+            focus_socket_conflict("hyprhist_focus.sock", &[], &MONITORS),
+            None
+        );
+        assert_eq!(
+            focus_socket_conflict("hyprhist_focus::DP-1.sock", &[], &MONITORS),
+            Some("tracking a subset of monitors".to_string())
+        );
+    }
+
+    #[test]
+    fn sockets_with_invalid_selectors_conflict() {
+        assert!(
+            focus_socket_conflict(
+                "hyprhist_focus::re:(.sock",
+                &selectors(&["DP-1"]),
+                &MONITORS
+            )
+            .is_some()
+        );
+    }
+
+    #[test]
+    fn rejects_socket_paths_longer_than_sun_path() {
+        let path = format!("/tmp/{}", "a".repeat(MAX_SOCKET_PATH_LEN - 5));
+        assert!(check_socket_path_length(&path).is_ok());
+        assert!(check_socket_path_length(&format!("{path}a")).is_err());
+    }
+
+    #[tokio::test]
+    async fn binds_socket_owner_only() {
+        let socket_path = std::env::temp_dir().join(format!(
+            "hyprhist_test_{}_owner_only.sock",
+            std::process::id()
+        ));
+        let socket_path = socket_path.to_str().expect("temp dir is valid UTF-8");
+
+        let _listener = bind_owner_only(socket_path).expect("socket binds");
+        let mode = std::fs::metadata(socket_path)
+            .expect("socket exists")
+            .permissions()
+            .mode();
+        std::fs::remove_file(socket_path).expect("socket is removed");
+
+        assert_eq!(mode & 0o777, OWNER_ONLY_SOCKET_MODE);
+    }
+}
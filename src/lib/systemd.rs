@@ -0,0 +1,157 @@
+use std::{
+    env, io,
+    os::{
+        fd::{FromRawFd, OwnedFd, RawFd},
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram, UnixListener},
+    },
+};
+
+use anyhow::Context;
+use log::{debug, warn};
+use nix::{
+    fcntl::{FcntlArg, FdFlag, fcntl},
+    time::{ClockId, clock_gettime},
+};
+
+/// First file descriptor passed by the service manager, see `sd_listen_fds(3)`
+const LISTEN_FDS_START: RawFd = 3;
+
+fn notify_socket_addr(path: &str) -> io::Result<SocketAddr> {
+    match path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name),
+        None => SocketAddr::from_pathname(path),
+    }
+}
+
+fn notify_to(path: &str, state: &str) -> io::Result<()> {
+    let socket = UnixDatagram::unbound()?;
+    socket.send_to_addr(state.as_bytes(), &notify_socket_addr(path)?)?;
+    Ok(())
+}
+
+/// Sends `state` to the service manager when running as a `Type=notify` service, and does
+/// nothing otherwise
+fn notify(state: &str) {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+
+    match notify_to(&path.to_string_lossy(), state) {
+        Ok(()) => debug!("Notified service manager: {}", state.replace('\n', " ")),
+        Err(err) => warn!("Failed to notify service manager: {err}"),
+    }
+}
+
+pub fn notify_ready() {
+    notify("READY=1");
+}
+
+pub fn notify_stopping() {
+    notify("STOPPING=1");
+}
+
+/// Tells a `Type=notify-reload` service manager that a reload started; it's finished by
+/// [`notify_ready`]
+pub fn notify_reloading() {
+    match clock_gettime(ClockId::CLOCK_MONOTONIC) {
+        Ok(now) => {
+            let monotonic_usec = now.tv_sec() * 1_000_000 + now.tv_nsec() / 1_000;
+            notify(&format!("RELOADING=1\nMONOTONIC_USEC={monotonic_usec}"));
+        }
+        Err(err) => warn!("Failed to read monotonic clock: {err}"),
+    }
+}
+
+/// Number of sockets passed to this process, ignoring variables meant for another process
+fn passed_fd_count(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    pid: u32,
+) -> anyhow::Result<usize> {
+    let (Some(listen_pid), Some(listen_fds)) = (listen_pid, listen_fds) else {
+        return Ok(0);
+    };
+
+    let listen_pid: u32 = listen_pid
+        .parse()
+        .with_context(|| format!("Invalid LISTEN_PID '{listen_pid}'"))?;
+    if listen_pid != pid {
+        return Ok(0);
+    }
+
+    listen_fds
+        .parse()
+        .with_context(|| format!("Invalid LISTEN_FDS '{listen_fds}'"))
+}
+
+/// Takes the listening socket passed through `LISTEN_FDS` by socket activation, if any
+#[allow(clippy::missing_errors_doc)]
+pub fn activated_listener() -> anyhow::Result<Option<UnixListener>> {
+    let count = passed_fd_count(
+        env::var("LISTEN_PID").ok().as_deref(),
+        env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    )?;
+
+    if count == 0 {
+        return Ok(None);
+    }
+    if count > 1 {
+        warn!("Received {count} sockets from the service manager; using the first one");
+    }
+
+    // SAFETY: the service manager passes ownership of descriptors from LISTEN_FDS_START
+    // onwards to this process, and nothing else takes them
+    let fd = unsafe { OwnedFd::from_raw_fd(LISTEN_FDS_START) };
+    // Passed descriptors are inheritable, but processes the daemon spawns must not keep the
+    // socket open
+    fcntl(&fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+        .context("Failed to set close-on-exec on the activated socket")?;
+
+    let listener = UnixListener::from(fd);
+    listener
+        .local_addr()
+        .context("Activated socket is not a unix socket")?;
+    listener.set_nonblocking(true)?;
+
+    Ok(Some(listener))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixDatagram;
+
+    use super::{notify_to, passed_fd_count};
+
+    #[test]
+    fn counts_fds_passed_to_this_process() {
+        assert_eq!(
+            passed_fd_count(Some("42"), Some("2"), 42).expect("valid count"),
+            2
+        );
+        assert_eq!(
+            passed_fd_count(Some("41"), Some("2"), 42).expect("valid count"),
+            0
+        );
+        assert_eq!(passed_fd_count(None, None, 42).expect("valid count"), 0);
+        assert!(passed_fd_count(Some("42"), Some("many"), 42).is_err());
+    }
+
+    #[test]
+    fn sends_state_to_notify_socket() {
+        let dir = std::env::temp_dir().join(format!("hyprhist-notify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temporary directory");
+        let path = dir.join("notify.sock");
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).expect("bind notify socket");
+
+        notify_to(&path.to_string_lossy(), "READY=1").expect("send notification");
+
+        let mut buffer = [0; 64];
+        let length = receiver.recv(&mut buffer).expect("receive notification");
+        assert_eq!(&buffer[..length], b"READY=1");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,15 @@
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
-use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::watch;
 
-use crate::event_history::EventHistory;
+use crate::{
+    config::ActionsConfig, focus_history::FocusHistory, monitor_selector::MonitorSelector,
+    reload::Reloader, window_cache::WindowProperties,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SortedDistinctVec<T>(Vec<T>);
@@ -49,24 +52,27 @@ impl<'a, T> IntoIterator for &'a SortedDistinctVec<T> {
     }
 }
 
-pub type SharedEventHistory<T> = Arc<Mutex<EventHistory<T>>>;
-
 // Events
 pub trait EventItem {
     type ID: Eq + PartialEq + Hash + Clone + Display + Debug;
     fn get_id(&self) -> &Self::ID;
 }
 
+#[derive(Debug)]
 pub struct WindowEvent {
     pub address: String,
     pub monitor: Option<String>,
     pub time: NaiveDateTime,
+    pub properties: WindowProperties,
 }
 
 #[derive(Clone)]
 pub struct FocusEvents {
-    pub focus_events: SharedEventHistory<WindowEvent>,
-    pub requested_monitors: &'static SortedDistinctVec<String>,
+    pub focus_events: FocusHistory,
+    pub reloader: Reloader,
+    pub requested_monitors: watch::Receiver<SortedDistinctVec<MonitorSelector>>,
+    pub actions: watch::Receiver<ActionsConfig>,
+    pub reconcile_interval: Option<Duration>,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use hyprland::{
+    data::{Client, Clients, FullscreenMode, Workspaces},
+    shared::{HyprData, WorkspaceId},
+};
+use log::debug;
+use serde::Serialize;
+
+/// What's known about a window, recorded with every history entry and matched by window rules
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+// The flags are independent properties reported by Hyprland, not states of one machine
+#[allow(clippy::struct_excessive_bools)]
+pub struct WindowProperties {
+    pub workspace: String,
+    pub workspace_id: WorkspaceId,
+    pub class: String,
+    pub title: String,
+    pub initial_class: String,
+    pub initial_title: String,
+    pub pid: i32,
+    pub floating: bool,
+    pub fullscreen: bool,
+    pub pinned: bool,
+    pub xwayland: bool,
+}
+
+impl From<Client> for WindowProperties {
+    fn from(client: Client) -> Self {
+        Self {
+            workspace: client.workspace.name,
+            workspace_id: client.workspace.id,
+            class: client.class,
+            title: client.title,
+            initial_class: client.initial_class,
+            initial_title: client.initial_title,
+            pid: client.pid,
+            floating: client.floating,
+            fullscreen: client.fullscreen != FullscreenMode::None,
+            pinned: client.pinned,
+            xwayland: client.xwayland,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedWindow {
+    properties: WindowProperties,
+    /// Whether the properties come from a Hyprland snapshot, rather than from events which
+    /// don't report every property
+    complete: bool,
+}
+
+impl CachedWindow {
+    fn partial(properties: WindowProperties) -> Self {
+        Self {
+            properties,
+            complete: false,
+        }
+    }
+}
+
+/// In-memory view of every window's workspace and properties and which monitor every workspace
+/// is on, kept up to date from Hyprland events so focus events don't need a hyprctl round-trip
+#[derive(Debug, Default)]
+pub struct WindowCache {
+    windows: HashMap<String, CachedWindow>,
+    workspace_monitors: HashMap<String, String>,
+}
+
+impl WindowCache {
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn seeded() -> hyprland::Result<Self> {
+        let mut window_cache = Self::default();
+        window_cache.reseed().await?;
+        Ok(window_cache)
+    }
+
+    /// Replaces the cached state with a fresh snapshot of Hyprland's clients and workspaces
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn reseed(&mut self) -> hyprland::Result<()> {
+        let (clients, workspaces) =
+            tokio::try_join!(Clients::get_async(), Workspaces::get_async())?;
+
+        self.windows = clients
+            .into_iter()
+            .map(|client| {
+                (
+                    client.address.to_string(),
+                    CachedWindow {
+                        properties: client.into(),
+                        complete: true,
+                    },
+                )
+            })
+            .collect();
+        self.workspace_monitors = workspaces
+            .into_iter()
+            .map(|workspace| (workspace.name, workspace.monitor))
+            .collect();
+
+        debug!(
+            "Seeded window cache with {} windows on {} workspaces",
+            self.windows.len(),
+            self.workspace_monitors.len()
+        );
+
+        Ok(())
+    }
+
+    /// Replaces what's cached about the window at `address` with a fresh query of its client,
+    /// leaving every other window and workspace alone
+    #[allow(clippy::missing_errors_doc)]
+    pub async fn complete_window(&mut self, address: &str) -> hyprland::Result<()> {
+        let client = Clients::get_async()
+            .await?
+            .into_iter()
+            .find(|client| client.address.to_string() == address);
+
+        if let Some(client) = client {
+            self.windows.insert(
+                address.to_string(),
+                CachedWindow {
+                    properties: client.into(),
+                    complete: true,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn window_opened(
+        &mut self,
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    ) {
+        self.windows.insert(
+            address,
+            CachedWindow::partial(WindowProperties {
+                workspace,
+                initial_class: class.clone(),
+                class,
+                initial_title: title.clone(),
+                title,
+                ..WindowProperties::default()
+            }),
+        );
+    }
+
+    pub fn window_closed(&mut self, address: &str) {
+        self.windows.remove(address);
+    }
+
+    pub fn window_moved(&mut self, address: String, workspace: String, workspace_id: WorkspaceId) {
+        let properties = &mut self
+            .windows
+            .entry(address)
+            .or_insert_with(|| CachedWindow::partial(WindowProperties::default()))
+            .properties;
+        properties.workspace = workspace;
+        properties.workspace_id = workspace_id;
+    }
+
+    pub fn window_title_changed(&mut self, address: &str, title: String) {
+        if let Some(window) = self.windows.get_mut(address) {
+            window.properties.title = title;
+        }
+    }
+
+    pub fn window_floating_changed(&mut self, address: &str, floating: bool) {
+        if let Some(window) = self.windows.get_mut(address) {
+            window.properties.floating = floating;
+        }
+    }
+
+    pub fn window_fullscreen_changed(&mut self, address: &str, fullscreen: bool) {
+        if let Some(window) = self.windows.get_mut(address) {
+            window.properties.fullscreen = fullscreen;
+        }
+    }
+
+    pub fn window_pinned_changed(&mut self, address: &str, pinned: bool) {
+        if let Some(window) = self.windows.get_mut(address) {
+            window.properties.pinned = pinned;
+        }
+    }
+
+    pub fn workspace_moved(&mut self, workspace: String, monitor: String) {
+        self.workspace_monitors.insert(workspace, monitor);
+    }
+
+    pub fn workspace_removed(&mut self, workspace: &str) {
+        self.workspace_monitors.remove(workspace);
+    }
+
+    #[must_use]
+    pub fn contains_window(&self, address: &str) -> bool {
+        self.windows.contains_key(address)
+    }
+
+    /// Every cached window with its properties, in no particular order
+    pub fn windows(&self) -> impl Iterator<Item = (&str, &WindowProperties)> {
+        self.windows
+            .iter()
+            .map(|(address, window)| (address.as_str(), &window.properties))
+    }
+
+    #[must_use]
+    pub fn window_properties(&self, address: &str) -> Option<&WindowProperties> {
+        self.windows.get(address).map(|window| &window.properties)
+    }
+
+    /// Whether every property of the window at `address` is known
+    #[must_use]
+    pub fn has_complete_properties(&self, address: &str) -> bool {
+        self.windows
+            .get(address)
+            .is_some_and(|window| window.complete)
+    }
+
+    /// Monitor of the window at `address`, or `None` if either the window or its workspace is
+    /// unknown
+    #[must_use]
+    pub fn window_monitor(&self, address: &str) -> Option<&str> {
+        let workspace = &self.windows.get(address)?.properties.workspace;
+        self.workspace_monitors.get(workspace).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WindowCache;
+
+    fn open_window(window_cache: &mut WindowCache, address: &str, workspace: &str) {
+        window_cache.window_opened(
+            address.to_string(),
+            workspace.to_string(),
+            "kitty".to_string(),
+            "zsh".to_string(),
+        );
+    }
+
+    fn cache_with_window() -> WindowCache {
+        let mut window_cache = WindowCache::default();
+        window_cache.workspace_moved("1".to_string(), "DP-1".to_string());
+        window_cache.workspace_moved("2".to_string(), "HDMI-A-1".to_string());
+        open_window(&mut window_cache, "0x1", "1");
+        window_cache
+    }
+
+    #[test]
+    fn resolves_window_monitor_through_workspace() {
+        let window_cache = cache_with_window();
+
+        assert_eq!(window_cache.window_monitor("0x1"), Some("DP-1"));
+        assert_eq!(window_cache.window_monitor("0x2"), None);
+    }
+
+    #[test]
+    fn window_follows_moves_between_workspaces() {
+        let mut window_cache = cache_with_window();
+
+        window_cache.window_moved("0x1".to_string(), "2".to_string(), 2);
+
+        assert_eq!(window_cache.window_monitor("0x1"), Some("HDMI-A-1"));
+    }
+
+    #[test]
+    fn window_follows_its_workspace_between_monitors() {
+        let mut window_cache = cache_with_window();
+
+        window_cache.workspace_moved("1".to_string(), "HDMI-A-1".to_string());
+
+        assert_eq!(window_cache.window_monitor("0x1"), Some("HDMI-A-1"));
+    }
+
+    #[test]
+    fn closed_windows_and_removed_workspaces_are_forgotten() {
+        let mut window_cache = cache_with_window();
+        open_window(&mut window_cache, "0x2", "2");
+
+        window_cache.window_closed("0x1");
+        window_cache.workspace_removed("2");
+
+        assert!(!window_cache.contains_window("0x1"));
+        assert_eq!(window_cache.window_monitor("0x2"), None);
+    }
+
+    #[test]
+    fn property_events_update_opened_windows() {
+        let mut window_cache = cache_with_window();
+
+        window_cache.window_title_changed("0x1", "vim".to_string());
+        window_cache.window_floating_changed("0x1", true);
+
+        let properties = window_cache
+            .window_properties("0x1")
+            .expect("cached window");
+        assert_eq!(properties.title, "vim");
+        assert_eq!(properties.initial_class, "kitty");
+        assert!(properties.floating);
+        assert!(!window_cache.has_complete_properties("0x1"));
+    }
+}
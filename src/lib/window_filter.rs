@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{monitor_selector::REGEX_PREFIX, window_cache::WindowProperties};
+
+/// An exact property value, or a regex over it when prefixed with `re:`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ValuePattern {
+    source: String,
+    regex: Option<Regex>,
+}
+
+impl FromStr for ValuePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let regex = s
+            .strip_prefix(REGEX_PREFIX)
+            .map(Regex::new)
+            .transpose()
+            .map_err(|err| format!("invalid window rule regex: {err}"))?;
+
+        Ok(Self {
+            source: s.to_string(),
+            regex,
+        })
+    }
+}
+
+impl TryFrom<String> for ValuePattern {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ValuePattern> for String {
+    fn from(value: ValuePattern) -> Self {
+        value.source
+    }
+}
+
+impl PartialEq for ValuePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for ValuePattern {}
+
+impl ValuePattern {
+    #[must_use]
+    pub fn matches(&self, value: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(value),
+            None => self.source == value,
+        }
+    }
+}
+
+/// Window properties a rule requires; a window matches when every property that is set matches
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct WindowRule {
+    class: Option<ValuePattern>,
+    title: Option<ValuePattern>,
+    initial_class: Option<ValuePattern>,
+    workspace: Option<ValuePattern>,
+    floating: Option<bool>,
+    pinned: Option<bool>,
+    xwayland: Option<bool>,
+}
+
+impl WindowRule {
+    /// Whether no property is set, which would match every window
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    #[must_use]
+    pub fn matches(&self, properties: &WindowProperties) -> bool {
+        let pattern_matches = |pattern: &Option<ValuePattern>, value: &str| {
+            pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.matches(value))
+        };
+        let flag_matches = |flag: Option<bool>, value: bool| flag.is_none_or(|flag| flag == value);
+
+        pattern_matches(&self.class, &properties.class)
+            && pattern_matches(&self.title, &properties.title)
+            && pattern_matches(&self.initial_class, &properties.initial_class)
+            && pattern_matches(&self.workspace, &properties.workspace)
+            && flag_matches(self.floating, properties.floating)
+            && flag_matches(self.pinned, properties.pinned)
+            && flag_matches(self.xwayland, properties.xwayland)
+    }
+}
+
+/// Rules deciding which windows enter the focus history: a window has to match any include rule,
+/// or there must be none, and none of the exclude rules
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowFilters {
+    pub include: Vec<WindowRule>,
+    pub exclude: Vec<WindowRule>,
+}
+
+impl WindowFilters {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    #[must_use]
+    pub fn accepts(&self, properties: &WindowProperties) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|rule| rule.matches(properties)))
+            && !self.exclude.iter().any(|rule| rule.matches(properties))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValuePattern, WindowFilters, WindowRule};
+    use crate::window_cache::WindowProperties;
+
+    fn rule(source: &str) -> WindowRule {
+        toml::from_str(source).expect("valid rule")
+    }
+
+    fn window(class: &str, title: &str, floating: bool) -> WindowProperties {
+        WindowProperties {
+            workspace: "1".to_string(),
+            class: class.to_string(),
+            title: title.to_string(),
+            initial_class: class.to_string(),
+            floating,
+            ..WindowProperties::default()
+        }
+    }
+
+    #[test]
+    fn rule_requires_every_set_property() {
+        let floating_dialog = rule("class = \"re:^xdg-desktop-portal\"\nfloating = true");
+
+        assert!(floating_dialog.matches(&window("xdg-desktop-portal-gtk", "Open File", true)));
+        assert!(!floating_dialog.matches(&window("xdg-desktop-portal-gtk", "Open File", false)));
+        assert!(!floating_dialog.matches(&window("kitty", "Open File", true)));
+    }
+
+    #[test]
+    fn plain_values_match_exactly() {
+        let pip = rule("title = \"Picture-in-Picture\"");
+
+        assert!(pip.matches(&window("firefox", "Picture-in-Picture", true)));
+        assert!(!pip.matches(&window("firefox", "Picture-in-Picture - YouTube", true)));
+    }
+
+    #[test]
+    fn patterns_match_plain_values_or_regexes() {
+        let pattern = |source: &str| source.parse::<ValuePattern>().expect("valid pattern");
+        let browser = pattern("re:^(firefox|chromium)$");
+        let terminal = pattern("kitty");
+
+        assert!(browser.matches("chromium"));
+        assert!(!browser.matches("firefox-esr"));
+        assert!(terminal.matches("kitty"));
+        assert!(!terminal.matches("kitty-1"));
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let filters = WindowFilters {
+            include: vec![rule("class = \"re:^(firefox|kitty)$\"")],
+            exclude: vec![rule("title = \"Picture-in-Picture\"")],
+        };
+
+        assert!(filters.accepts(&window("kitty", "zsh", false)));
+        assert!(!filters.accepts(&window("firefox", "Picture-in-Picture", true)));
+        assert!(!filters.accepts(&window("polkit-gnome-authentication-agent-1", "", true)));
+    }
+
+    #[test]
+    fn rejects_invalid_regex() {
+        assert!(toml::from_str::<WindowRule>("class = \"re:(\"").is_err());
+    }
+}
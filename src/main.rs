@@ -1,28 +1,146 @@
-use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::Context;
 use clap::Parser;
 use env_logger::Env;
-use tokio::sync::Mutex;
+use log::{error, info};
+use tokio::sync::watch;
 
 use lib::{
-    cli::{Cli, Command, DaemonArgs, DaemonCommand},
+    cli::{Cli, Command, ConfigCommand, DaemonCommand},
+    config::{self, Config, DaemonSettings},
     daemon,
     event_history::EventHistory,
-    hypr_utils::current_focused_window_event,
-    socket,
-    types::{FocusEvents, HyprEvents, SharedEventHistory, SortedDistinctVec, WindowEvent},
+    focus_history::FocusHistory,
+    hypr_utils::{current_focused_window_event, window_on_requested_monitor},
+    marks::Marks,
+    monitor_selector::TrackedMonitors,
+    reload::{ConfigSource, Reloader},
+    shutdown::Shutdown,
+    socket, systemd,
+    types::{FocusEvents, HyprEvents, SortedDistinctVec},
+    window_cache::WindowCache,
 };
 
-fn shared_mutex<T>(of: T) -> Arc<Mutex<T>> {
-    Arc::new(Mutex::new(of))
+#[cfg(feature = "dbus")]
+async fn serve_dbus(hypr_events: HyprEvents, dbus: bool) -> anyhow::Result<()> {
+    if dbus {
+        lib::dbus::serve(hypr_events).await
+    } else {
+        Ok(())
+    }
 }
 
-fn window_on_requested_monitor(window_event: &WindowEvent, requested_monitors: &[String]) -> bool {
-    requested_monitors.is_empty()
-        || window_event
-            .monitor
-            .as_ref()
-            .is_some_and(|event_monitor| requested_monitors.contains(event_monitor))
+#[cfg(not(feature = "dbus"))]
+#[allow(clippy::unused_async)] // Same signature as the D-Bus build
+async fn serve_dbus(_hypr_events: HyprEvents, dbus: bool) -> anyhow::Result<()> {
+    if dbus {
+        log::warn!("D-Bus is enabled in the configuration, but hyprhist was built without it");
+    }
+    Ok(())
+}
+
+/// Runs the focus daemon until it's asked to shut down
+async fn run_focus_daemon(config_source: ConfigSource) -> anyhow::Result<()> {
+    let settings = config_source.settings()?;
+    let DaemonSettings {
+        requested_monitors,
+        history_size,
+        reconcile_interval,
+        allowed_uids,
+        dbus,
+        window_filters,
+        marks_path,
+        actions,
+    } = settings.clone();
+
+    let requested_monitors = SortedDistinctVec::new(requested_monitors);
+
+    let mut tracked_monitors = TrackedMonitors::new(requested_monitors.clone());
+    if let Err(err) = tracked_monitors.refresh().await {
+        error!("Failed to resolve tracked monitors: {err}");
+    }
+
+    let window_cache = WindowCache::seeded().await.unwrap_or_else(|err| {
+        error!("Failed to seed window cache: {err}");
+        WindowCache::default()
+    });
+
+    let mut marks = Marks::load(marks_path);
+    marks.resolve(&window_cache);
+
+    let event_history = match current_focused_window_event().await {
+        Some(window_event)
+            if window_on_requested_monitor(&window_event, &tracked_monitors)
+                && window_filters.accepts(&window_event.properties) =>
+        {
+            EventHistory::bootstrap(window_event, history_size)
+        }
+        _ => EventHistory::new(history_size),
+    };
+
+    // Every Hyprland event and focus command is processed by this one task, in the order they
+    // arrive
+    let (focus_events, focus_history_actor) = FocusHistory::new(
+        event_history,
+        window_cache,
+        tracked_monitors,
+        window_filters,
+        marks,
+    );
+
+    let (requested_monitors_sender, requested_monitors) = watch::channel(requested_monitors);
+    let (actions_sender, actions) = watch::channel(actions);
+    let (reloader, reload_task) = Reloader::new(
+        config_source,
+        settings,
+        focus_events.clone(),
+        requested_monitors_sender,
+        actions_sender,
+    );
+
+    let hypr_events: HyprEvents = HyprEvents::Focus(FocusEvents {
+        focus_events,
+        reloader,
+        requested_monitors,
+        actions,
+        reconcile_interval: (reconcile_interval > 0)
+            .then(|| Duration::from_secs(reconcile_interval)),
+    });
+
+    let allowed_uids: SortedDistinctVec<u32> = SortedDistinctVec::new(allowed_uids);
+    let activated_listener = systemd::activated_listener()?;
+
+    let shutdown = Shutdown::on_signals()?;
+
+    // The socket listener is the only task that returns on shutdown; once it has drained its
+    // connections the focus history, reloader, event listener and D-Bus service are dropped
+    tokio::select! {
+        result = async {
+            tokio::try_join!(
+                async {
+                    focus_history_actor.run().await;
+                    Ok(())
+                },
+                reload_task.run(),
+                daemon::run(hypr_events.clone()),
+                serve_dbus(hypr_events.clone(), dbus)
+            )
+        } => {
+            result?;
+        }
+        result = socket::listen(
+            hypr_events.clone(),
+            Box::leak(Box::new(allowed_uids)),
+            activated_listener,
+            shutdown,
+        ) => {
+            result?;
+            info!("Daemon shut down");
+        }
+    }
+
+    Ok(())
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -31,40 +149,37 @@ async fn main() -> anyhow::Result<()> {
 
     let cli: &'static Cli = Box::leak(Box::new(Cli::parse()));
 
+    let config_path = cli.config.clone().or_else(config::default_config_path);
+
     match &cli.command {
         Command::Daemon { command } => match command {
-            DaemonCommand::Focus(DaemonArgs {
-                requested_monitors,
-                history_size,
-            }) => {
-                let focus_events: SharedEventHistory<WindowEvent> = {
-                    let event_history = match current_focused_window_event().await {
-                        Some(window_event)
-                            if window_on_requested_monitor(&window_event, requested_monitors) =>
-                        {
-                            EventHistory::bootstrap(window_event, *history_size)
-                        }
-                        _ => EventHistory::new(*history_size),
-                    };
-
-                    shared_mutex(event_history)
-                };
-
-                let requested_monitors: SortedDistinctVec<String> =
-                    SortedDistinctVec::new(requested_monitors.clone());
-
-                let hypr_events: HyprEvents = HyprEvents::Focus(FocusEvents {
-                    focus_events,
-                    requested_monitors: Box::leak(Box::new(requested_monitors)),
-                });
-
-                tokio::try_join!(
-                    daemon::run(hypr_events.clone()),
-                    socket::listen(hypr_events)
-                )?;
+            DaemonCommand::Focus(daemon_args) => {
+                run_focus_daemon(ConfigSource {
+                    config_path,
+                    daemon_args,
+                })
+                .await?;
             }
         },
-        Command::Focus { command } => socket::send_focus_command(command).await?,
+        Command::Focus { command } => {
+            socket::send_focus_command(command, &Config::load_or_default(config_path.as_deref())?)
+                .await?;
+        }
+        Command::Mark { command } => {
+            socket::send_mark_command(command, &Config::load_or_default(config_path.as_deref())?)
+                .await?;
+        }
+        Command::Jump(args) => {
+            socket::send_jump_command(args, &Config::load_or_default(config_path.as_deref())?)
+                .await?;
+        }
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => {
+            config::check(
+                &config_path.context("Could not determine the configuration file path")?,
+            )?;
+        }
     }
 
     Ok(())